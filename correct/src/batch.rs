@@ -0,0 +1,174 @@
+#![doc="
+    Parallel batch correction for large inputs. The input is read whole, every distinct word is
+    corrected once (real text repeats its words a lot, typos included), the distinct words that
+    need any work are shared out between worker threads, and the output is written in input
+    order.
+
+    Workers take the next word from a shared counter rather than fixed slices, so one slow word
+    (a long unknown word has many edits) doesn't hold up the rest of its slice.
+"]
+
+use std::collections::{HashMap, HashSet};
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+
+use corrector::Corrector;
+use suggest::word_json;
+
+/// Distinct items, in order of first appearance
+pub fn unique<'a, I: IntoIterator<Item = &'a str>>(items: I) -> Vec<&'a str> {
+    let mut seen = HashSet::new();
+    let mut found = Vec::new();
+    for item in items {
+        if seen.insert(item) {
+            found.push(item);
+        }
+    }
+    found
+}
+
+/// `f` applied to every item on `threads` worker threads, results in the items' order
+pub fn map_parallel<T, F>(items: &[&str], threads: usize, f: F) -> Vec<T>
+    where T: Send, F: Fn(&str) -> T + Sync
+{
+    let next = AtomicUsize::new(0);
+    let threads = threads.clamp(1, items.len().max(1));
+
+    let mut results: Vec<(usize, T)> = thread::scope(|scope| {
+        let workers: Vec<_> = (0..threads).map(|_| scope.spawn(|| {
+            let mut done = Vec::new();
+            loop {
+                let i = next.fetch_add(1, Ordering::Relaxed);
+                if i >= items.len() {
+                    return done;
+                }
+                done.push((i, f(items[i])));
+            }
+        })).collect();
+        workers.into_iter().flat_map(|w| w.join().expect("batch worker panicked")).collect()
+    });
+
+    results.sort_by_key(|&(i, _)| i);
+    results.into_iter().map(|(_, result)| result).collect()
+}
+
+/// Line mode output for one word: the word if it is accepted, else "word, correction" (or
+/// "word, -" when nothing is close enough). A correction below the corrector's minimum
+/// confidence is flagged for review instead: "word, correction (review, confidence 0.42)".
+pub fn line_result(word: &str, corrector: &Corrector) -> String {
+    if corrector.is_accepted(word) {
+        return word.to_owned();
+    }
+    match corrector.correct_with_confidence(word) {
+        Some((best, confidence)) if confidence < corrector.min_confidence() =>
+            format!("{}, {} (review, confidence {:.2})", word, best, confidence),
+        Some((best, _)) => format!("{}, {}", word, best),
+        None => format!("{}, -", word),
+    }
+}
+
+fn read_lines<R: Read>(reader: R) -> io::Result<Vec<String>> {
+    BufReader::new(reader).lines().collect()
+}
+
+/// Line mode over the whole input, correcting each distinct unknown word once
+pub fn correct_lines<R: Read, W: Write>(reader: R, mut writer: W, corrector: &Corrector, threads: usize)
+        -> io::Result<()> {
+    let lines = read_lines(reader)?;
+    let unknown: Vec<&str> = unique(lines.iter().map(|l| l.as_str()))
+        .into_iter()
+        .filter(|w| !corrector.is_accepted(w))
+        .collect();
+    let corrected = map_parallel(&unknown, threads, |word| line_result(word, corrector));
+    let cache: HashMap<&str, String> = unknown.into_iter().zip(corrected).collect();
+
+    for line in &lines {
+        match cache.get(line.as_str()) {
+            Some(result) => writeln!(writer, "{}", result)?,
+            None => writeln!(writer, "{}", line)?,
+        }
+    }
+    writer.flush()
+}
+
+/// Like suggest::batch, computing each distinct word's JSON once
+pub fn json_lines<R: Read, W: Write>(reader: R, mut writer: W, corrector: &Corrector, threads: usize)
+        -> io::Result<()> {
+    let lines = read_lines(reader)?;
+    let words: Vec<&str> = lines.iter().map(|l| l.trim()).filter(|w| !w.is_empty()).collect();
+    let distinct = unique(words.iter().cloned());
+    let json = map_parallel(&distinct, threads, |word| word_json(word, corrector).to_string());
+    let cache: HashMap<&str, String> = distinct.into_iter().zip(json).collect();
+
+    for word in words {
+        writeln!(writer, "{}", cache[word])?;
+    }
+    writer.flush()
+}
+
+#[cfg(test)]
+mod batch_tests {
+    use super::{correct_lines, json_lines, line_result, map_parallel, unique};
+    use corrector::Corrector;
+    use suggest::batch;
+    use table::form_table;
+
+    fn corrector() -> Corrector {
+        Corrector::new(form_table("hello hello hell world the quick brown fox".as_bytes()))
+    }
+
+    #[test]
+    fn unique_keeps_first_order() {
+        assert_eq!(unique(vec!["b", "a", "b", "c", "a"]), vec!["b", "a", "c"]);
+        assert!(unique(Vec::new()).is_empty());
+    }
+
+    #[test]
+    fn map_parallel_preserves_order() {
+        let items: Vec<String> = (0..200).map(|i| i.to_string()).collect();
+        let items: Vec<&str> = items.iter().map(|s| s.as_str()).collect();
+        for threads in &[0, 1, 3, 16, 500] {
+            let lengths = map_parallel(&items, *threads, |s| s.parse::<usize>().unwrap() * 2);
+            assert_eq!(lengths, (0..200).map(|i| i * 2).collect::<Vec<usize>>());
+        }
+        assert!(map_parallel(&[], 4, |s| s.len()).is_empty());
+    }
+
+    #[test]
+    fn correct_lines_test() {
+        let mut out = Vec::new();
+        correct_lines("helo\nworld\nqiuck\nhelo\nzzzzzzz\n\nhelo\n".as_bytes(), &mut out, &corrector(), 4).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(),
+                   "helo, hello\nworld\nqiuck, quick\nhelo, hello\nzzzzzzz, -\n, -\nhelo, hello\n");
+    }
+
+    #[test]
+    fn line_result_ignores_and_restores_case() {
+        let corrector = corrector();
+        assert_eq!(line_result("Hello", &corrector), "Hello");
+        assert_eq!(line_result("WORLD", &corrector), "WORLD");
+        assert_eq!(line_result("Helo", &corrector), "Helo, Hello");
+        assert_eq!(line_result("QIUCK", &corrector), "QIUCK, QUICK");
+        assert_eq!(line_result("HElo", &corrector), "HElo, HEllo");
+        assert_eq!(line_result("Zzzzzzz", &corrector), "Zzzzzzz, -");
+    }
+
+    #[test]
+    fn line_result_flags_low_confidence() {
+        let mut corrector = corrector();
+        corrector.set_min_confidence(0.7);
+        assert_eq!(line_result("helo", &corrector), "helo, hello (review, confidence 0.67)");
+        assert_eq!(line_result("wrld", &corrector), "wrld, world");
+    }
+
+    #[test]
+    fn json_lines_matches_sequential_batch() {
+        let input = "helo\nworld\n\nhelo\nbrwn\nworld\n";
+        let mut sequential = Vec::new();
+        batch(input.as_bytes(), &mut sequential, &corrector()).unwrap();
+        let mut parallel = Vec::new();
+        json_lines(input.as_bytes(), &mut parallel, &corrector(), 3).unwrap();
+        assert_eq!(String::from_utf8(parallel).unwrap(), String::from_utf8(sequential).unwrap());
+    }
+}
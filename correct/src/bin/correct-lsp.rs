@@ -0,0 +1,59 @@
+extern crate correct;
+
+use std::env;
+use std::io::{stdin, stdout, BufWriter};
+use std::process;
+
+use correct::corrector::Corrector;
+use correct::lsp::Server;
+use correct::model::Model;
+use correct::phonetic::DEFAULT_PHONETIC_WEIGHT;
+
+#[doc="
+    Spell checking language server. Loads a model (or trains on a text file) once, then speaks
+    LSP over stdin/stdout: diagnostics for misspelled words in plain text, Markdown, and the
+    comments and strings of code, with quick fixes for the top suggestions.
+
+    Editors start it as `correct-lsp model.bin`, with the same --errors, --dict and --ignore
+    files as `correct`. A phonetic index stored by `correct train --phonetic` is used. Nothing
+    but LSP messages is written to stdout.
+"]
+fn main() {
+    let mut args = env::args().skip(1);
+    let mut train = None;
+    let mut errors = None;
+    let mut dict = None;
+    let mut ignore = None;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--errors" => errors = Some(args.next().unwrap_or_else(|| fail(USAGE))),
+            "--dict" => dict = Some(args.next().unwrap_or_else(|| fail(USAGE))),
+            "--ignore" => ignore = Some(args.next().unwrap_or_else(|| fail(USAGE))),
+            _ if train.is_none() && !arg.starts_with('-') => train = Some(arg),
+            _ => fail(USAGE),
+        }
+    }
+    let train = train.unwrap_or_else(|| fail(USAGE));
+
+    let Model { table, phonetic, .. } = Model::open(&train)
+        .unwrap_or_else(|e| fail(&format!("Error loading {}: {}", train, e)));
+    let mut corrector = Corrector::from_files(table, errors.as_deref(), dict.as_deref(), ignore.as_deref())
+        .unwrap_or_else(|e| fail(&format!("Error loading {}", e)));
+    if let Some(index) = phonetic {
+        corrector.set_phonetic_index(index, DEFAULT_PHONETIC_WEIGHT);
+    }
+
+    let input = stdin();
+    let code = Server::new(&corrector).run(input.lock(), BufWriter::new(stdout()))
+        .unwrap_or_else(|e| fail(&format!("Error in LSP session: {}", e)));
+    process::exit(code);
+}
+
+static USAGE: &str = "Usage: correct-lsp [train file or model] [--errors pairs file] [--dict words file]
+                   [--ignore patterns file]";
+
+fn fail(message: &str) -> ! {
+    eprintln!("{}", message);
+    process::exit(1);
+}
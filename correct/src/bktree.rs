@@ -0,0 +1,186 @@
+#![doc="
+    Candidate search with a BK-tree, as an alternative to generating every edit of the typed
+    word. Generating edits is capped at two, and the number of edits grows with the alphabet
+    size and the word length; a BK-tree finds the known words within any distance by measuring
+    the distance to a fraction of the vocabulary.
+
+    Every child of a node sits at a known distance from it, and by the triangle inequality only
+    the children at distance d - r ..= d + r of a node at distance d can hold words within r of
+    the query. That needs a true metric, so the distance is the unrestricted Damerau-Levenshtein
+    distance (insertions, deletions, substitutions and transpositions of adjacent characters,
+    which may be edited again), rather than the restricted optimal string alignment distance
+    of the channel module, which breaks the triangle inequality (ca -> abc).
+"]
+
+use std::cmp::min;
+use std::collections::HashMap;
+
+struct Node {
+    word: String,
+    /// (distance to this node's word, child node)
+    children: Vec<(usize, usize)>,
+}
+
+pub struct BkTree {
+    nodes: Vec<Node>,
+}
+
+impl BkTree {
+    pub fn new<'a, I: IntoIterator<Item = &'a String>>(words: I) -> BkTree {
+        let mut tree = BkTree { nodes: Vec::new() };
+        // Sorted so the tree, and the order of results, don't depend on hashing
+        let mut words: Vec<&String> = words.into_iter().collect();
+        words.sort();
+        for word in words {
+            tree.insert(word);
+        }
+        tree
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    pub fn insert(&mut self, word: &str) {
+        let new = self.nodes.len();
+        if self.nodes.is_empty() {
+            self.nodes.push(Node { word: word.to_owned(), children: Vec::new() });
+            return;
+        }
+
+        let mut current = 0;
+        loop {
+            let distance = damerau_levenshtein(word, &self.nodes[current].word);
+            if distance == 0 {
+                return;
+            }
+            match self.nodes[current].children.iter().find(|&&(d, _)| d == distance) {
+                Some(&(_, child)) => current = child,
+                None => {
+                    self.nodes[current].children.push((distance, new));
+                    self.nodes.push(Node { word: word.to_owned(), children: Vec::new() });
+                    return;
+                }
+            }
+        }
+    }
+
+    /// Every word within `max_distance` of `word`, with its distance, closest first
+    pub fn find(&self, word: &str, max_distance: usize) -> Vec<(&str, usize)> {
+        let mut found = Vec::new();
+        let mut pending = if self.nodes.is_empty() { vec![] } else { vec![0] };
+
+        while let Some(current) = pending.pop() {
+            let node = &self.nodes[current];
+            let distance = damerau_levenshtein(word, &node.word);
+            if distance <= max_distance {
+                found.push((node.word.as_str(), distance));
+            }
+            let low = distance.saturating_sub(max_distance);
+            let high = distance + max_distance;
+            pending.extend(node.children.iter().filter(|&&(d, _)| low <= d && d <= high).map(|&(_, child)| child));
+        }
+        found.sort_by(|a, b| a.1.cmp(&b.1).then_with(|| a.0.cmp(b.0)));
+        found
+    }
+}
+
+/// Unrestricted Damerau-Levenshtein distance over chars (Lowrance-Wagner)
+pub fn damerau_levenshtein(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let infinity = a.len() + b.len();
+    // d[i + 1][j + 1] is the distance between a[..i] and b[..j]; row and column 0 are sentinels
+    let mut d = vec![vec![0; b.len() + 2]; a.len() + 2];
+    d[0][0] = infinity;
+    for i in 0..=a.len() {
+        d[i + 1][0] = infinity;
+        d[i + 1][1] = i;
+    }
+    for j in 0..=b.len() {
+        d[0][j + 1] = infinity;
+        d[1][j + 1] = j;
+    }
+
+    // Last row where each character was seen in a
+    let mut last_row: HashMap<char, usize> = HashMap::new();
+    for i in 1..=a.len() {
+        let mut last_match_column = 0;
+        for j in 1..=b.len() {
+            let k = *last_row.get(&b[j - 1]).unwrap_or(&0);
+            let l = last_match_column;
+            let cost = if a[i - 1] == b[j - 1] {
+                last_match_column = j;
+                0
+            } else {
+                1
+            };
+            d[i + 1][j + 1] = min(
+                min(d[i][j] + cost, d[i + 1][j] + 1),
+                min(d[i][j + 1] + 1, d[k][l] + (i - k - 1) + 1 + (j - l - 1)),
+            );
+        }
+        last_row.insert(a[i - 1], i);
+    }
+    d[a.len() + 1][b.len() + 1]
+}
+
+#[cfg(test)]
+mod bktree_tests {
+    use super::{damerau_levenshtein, BkTree};
+    use channel::edit_distance;
+
+    #[test]
+    fn distance_test() {
+        assert_eq!(damerau_levenshtein("", ""), 0);
+        assert_eq!(damerau_levenshtein("abc", ""), 3);
+        assert_eq!(damerau_levenshtein("kitten", "sitting"), 3);
+        assert_eq!(damerau_levenshtein("teh", "the"), 1);
+        assert_eq!(damerau_levenshtein("café", "cafe"), 1);
+        // Unlike the optimal string alignment distance, a transposed pair can be edited again
+        assert_eq!(damerau_levenshtein("ca", "abc"), 2);
+        assert_eq!(edit_distance("ca", "abc"), 3);
+    }
+
+    #[test]
+    fn find_test() {
+        let words: Vec<String> = ["hello", "help", "hell", "shell", "world", "word", "held"]
+            .iter().map(|w| w.to_string()).collect();
+        let tree = BkTree::new(&words);
+        assert_eq!(tree.len(), 7);
+        assert_eq!(tree.find("helo", 1), vec![("held", 1), ("hell", 1), ("hello", 1), ("help", 1)]);
+        assert_eq!(tree.find("wrld", 1), vec![("world", 1)]);
+        assert_eq!(tree.find("wrld", 2), vec![("world", 1), ("held", 2), ("word", 2)]);
+        assert_eq!(tree.find("hello", 0), vec![("hello", 0)]);
+    }
+
+    #[test]
+    fn find_matches_brute_force() {
+        let words: Vec<String> = "the quick brown fox jumps over the lazy dog and then some other words \
+                                  like there their three throw through thorough though"
+            .split_whitespace().map(|w| w.to_owned()).collect();
+        let tree = BkTree::new(&words);
+        for query in &["thru", "teh", "qiuck", "thorugh", "x", "somewhere"] {
+            for radius in 0..5 {
+                let mut expected: Vec<(&str, usize)> = words.iter()
+                    .map(|w| (w.as_str(), damerau_levenshtein(query, w)))
+                    .filter(|&(_, d)| d <= radius)
+                    .collect();
+                expected.sort_by(|a, b| a.1.cmp(&b.1).then_with(|| a.0.cmp(b.0)));
+                expected.dedup();
+                assert_eq!(tree.find(query, radius), expected, "{} within {}", query, radius);
+            }
+        }
+    }
+
+    #[test]
+    fn empty_tree() {
+        let tree = BkTree::new(&[]);
+        assert!(tree.is_empty());
+        assert!(tree.find("anything", 3).is_empty());
+    }
+}
@@ -0,0 +1,282 @@
+#![doc="
+    Noisy-channel error model. Scores how likely it is that someone who meant to type `intended`
+    actually typed `typed`, as the product of the probabilities of the individual edits between
+    them. Without training every edit is equally likely; trained from a file of
+    misspelling/correction pairs, the model becomes a confusion matrix over edits, with a prior
+    that favours substituting keys that are adjacent on a QWERTY keyboard.
+
+    Accepted pair file lines:
+        - misspelling -> correction
+        - correction: misspelling misspelling ...      (Norvig's test set format)
+        - misspelling correction
+"]
+
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Read};
+
+/// Pseudo-count given to every edit, seen or not
+const SMOOTHING: f64 = 1.0;
+/// Extra pseudo-count given to substitutions of neighbouring keys
+const ADJACENT_KEY_PRIOR: f64 = 2.0;
+static KEYBOARD_ROWS: [&str; 3] = ["qwertyuiop", "asdfghjkl", "zxcvbnm"];
+
+/// A single edit turning the intended word into the typed one
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Edit {
+    /// intended letter was left out
+    Delete(char),
+    /// an extra letter was typed
+    Insert(char),
+    /// (intended, typed)
+    Substitute(char, char),
+    /// intended pair (a, b) was typed as (b, a)
+    Transpose(char, char),
+}
+
+pub struct ErrorModel {
+    counts: HashMap<Edit, usize>,
+    total: usize,
+    trained: bool,
+}
+
+impl ErrorModel {
+    /// Every edit is equally likely, so candidates at the same distance rank by count alone
+    pub fn uniform() -> ErrorModel {
+        ErrorModel {
+            counts: HashMap::new(),
+            total: 0,
+            trained: false,
+        }
+    }
+
+    /// Learns edit counts from a file of misspelling/correction pairs
+    pub fn from_pairs<R: Read>(reader: R) -> ErrorModel {
+        let mut model = ErrorModel {
+            counts: HashMap::new(),
+            total: 0,
+            trained: true,
+        };
+
+        for (typed, intended) in read_pairs(reader) {
+            for edit in alignment(&typed, &intended) {
+                model.add_edit(edit);
+            }
+        }
+        model
+    }
+
+    pub fn add_edit(&mut self, edit: Edit) {
+        *self.counts.entry(edit).or_insert(0) += 1;
+        self.total += 1;
+    }
+
+    /// Smoothed probability of a single edit
+    pub fn edit_probability(&self, edit: &Edit) -> f64 {
+        if !self.trained {
+            return 1.0;
+        }
+
+        let mut pseudo = SMOOTHING;
+        if let Edit::Substitute(a, b) = *edit {
+            if keyboard_adjacent(a, b) {
+                pseudo += ADJACENT_KEY_PRIOR;
+            }
+        }
+
+        let count = *self.counts.get(edit).unwrap_or(&0) as f64;
+        let kinds = (self.counts.len() + 1) as f64;
+        (count + pseudo) / (self.total as f64 + SMOOTHING * kinds + ADJACENT_KEY_PRIOR)
+    }
+
+    /// P(typed | intended), the product over the edits of the best alignment
+    pub fn probability(&self, typed: &str, intended: &str) -> f64 {
+        alignment(typed, intended).iter()
+            .map(|edit| self.edit_probability(edit))
+            .product()
+    }
+}
+
+/// Whether two letters sit next to each other on a QWERTY keyboard
+pub fn keyboard_adjacent(a: char, b: char) -> bool {
+    let position = |c: char| {
+        KEYBOARD_ROWS.iter().enumerate()
+            .filter_map(|(row, keys)| keys.find(c).map(|col| (row as isize, col as isize)))
+            .next()
+    };
+
+    match (position(a), position(b)) {
+        (Some((ra, ca)), Some((rb, cb))) => {
+            a != b && (ra - rb).abs() <= 1 && (ca - cb).abs() <= 1
+        }
+        _ => false,
+    }
+}
+
+/// Number of deletions, insertions, substitutions and adjacent transpositions needed to turn
+/// one word into the other (optimal string alignment distance)
+pub fn edit_distance(a: &str, b: &str) -> usize {
+    alignment(a, b).len()
+}
+
+/// The cheapest sequence of edits turning `intended` into `typed`
+pub fn alignment(typed: &str, intended: &str) -> Vec<Edit> {
+    let t: Vec<char> = typed.chars().collect();
+    let w: Vec<char> = intended.chars().collect();
+    let mut d = vec![vec![0usize; t.len() + 1]; w.len() + 1];
+
+    for (i, row) in d.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for (j, cell) in d[0].iter_mut().enumerate() {
+        *cell = j;
+    }
+
+    for i in 1..w.len() + 1 {
+        for j in 1..t.len() + 1 {
+            let cost = if w[i - 1] == t[j - 1] { 0 } else { 1 };
+            let mut best = (d[i - 1][j] + 1).min(d[i][j - 1] + 1).min(d[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && w[i - 1] == t[j - 2] && w[i - 2] == t[j - 1] {
+                best = best.min(d[i - 2][j - 2] + 1);
+            }
+            d[i][j] = best;
+        }
+    }
+
+    // Walk back from the corner, preferring matches, then substitutions
+    let mut edits = Vec::new();
+    let (mut i, mut j) = (w.len(), t.len());
+    while i > 0 || j > 0 {
+        if i > 0 && j > 0 && w[i - 1] == t[j - 1] && d[i][j] == d[i - 1][j - 1] {
+            i -= 1;
+            j -= 1;
+        } else if i > 0 && j > 0 && d[i][j] == d[i - 1][j - 1] + 1 {
+            edits.push(Edit::Substitute(w[i - 1], t[j - 1]));
+            i -= 1;
+            j -= 1;
+        } else if i > 1 && j > 1 && w[i - 1] == t[j - 2] && w[i - 2] == t[j - 1]
+                && d[i][j] == d[i - 2][j - 2] + 1 {
+            edits.push(Edit::Transpose(w[i - 2], w[i - 1]));
+            i -= 2;
+            j -= 2;
+        } else if i > 0 && d[i][j] == d[i - 1][j] + 1 {
+            edits.push(Edit::Delete(w[i - 1]));
+            i -= 1;
+        } else {
+            edits.push(Edit::Insert(t[j - 1]));
+            j -= 1;
+        }
+    }
+    edits.reverse();
+    edits
+}
+
+/// Reads (misspelling, correction) pairs, lowercased. Besides one pair per line, accepts the
+/// Birkbeck format: a `$correction` line followed by its misspellings, one per line.
+pub fn read_pairs<R: Read>(reader: R) -> Vec<(String, String)> {
+    let mut pairs = Vec::new();
+    let mut birkbeck: Option<String> = None;
+
+    for line in BufReader::new(reader).lines() {
+        let line = match line {
+            Ok(line) => line.to_lowercase(),
+            Err(_) => break,
+        };
+
+        if let Some(intended) = line.trim().strip_prefix('$') {
+            birkbeck = Some(intended.to_owned());
+        } else if let Some(arrow) = line.find("->") {
+            let typed = line[..arrow].trim();
+            let intended = line[arrow + 2..].trim();
+            if !typed.is_empty() && !intended.is_empty() {
+                pairs.push((typed.to_owned(), intended.to_owned()));
+            }
+        } else if let Some(colon) = line.find(':') {
+            let intended = line[..colon].trim();
+            for typed in line[colon + 1..].split_whitespace() {
+                pairs.push((typed.to_owned(), intended.to_owned()));
+            }
+        } else {
+            let words: Vec<&str> = line.split_whitespace().collect();
+            match (words.len(), &birkbeck) {
+                (2, _) => pairs.push((words[0].to_owned(), words[1].to_owned())),
+                (1, Some(intended)) => pairs.push((words[0].to_owned(), intended.clone())),
+                _ => (),
+            }
+        }
+    }
+    pairs
+}
+
+#[cfg(test)]
+mod channel_tests {
+    use super::{alignment, edit_distance, keyboard_adjacent, read_pairs, Edit, ErrorModel};
+
+    #[test]
+    fn alignment_test() {
+        assert_eq!(alignment("speling", "spelling"), vec![Edit::Delete('l')]);
+        assert_eq!(alignment("helllo", "hello"), vec![Edit::Insert('l')]);
+        assert_eq!(alignment("hallo", "hello"), vec![Edit::Substitute('e', 'a')]);
+        assert_eq!(alignment("teh", "the"), vec![Edit::Transpose('h', 'e')]);
+        assert!(alignment("same", "same").is_empty());
+    }
+
+    #[test]
+    fn edit_distance_test() {
+        assert_eq!(edit_distance("abcd", "abcd"), 0);
+        assert_eq!(edit_distance("abcd", "bacd"), 1);
+        assert_eq!(edit_distance("abcd", "aabed"), 2);
+        assert_eq!(edit_distance("", "abc"), 3);
+    }
+
+    #[test]
+    fn keyboard_adjacent_test() {
+        assert!(keyboard_adjacent('a', 's'));
+        assert!(keyboard_adjacent('e', 'r'));
+        assert!(keyboard_adjacent('g', 'b'));
+        assert!(!keyboard_adjacent('a', 'p'));
+        assert!(!keyboard_adjacent('a', 'a'));
+    }
+
+    #[test]
+    fn read_pairs_test() {
+        let input = "speling -> spelling\nthe: teh hte\nrecieve receive\n\n";
+        let pairs = read_pairs(input.as_bytes());
+        assert_eq!(pairs, vec![
+            ("speling".to_owned(), "spelling".to_owned()),
+            ("teh".to_owned(), "the".to_owned()),
+            ("hte".to_owned(), "the".to_owned()),
+            ("recieve".to_owned(), "receive".to_owned()),
+        ]);
+    }
+
+    #[test]
+    fn read_pairs_birkbeck() {
+        let pairs = read_pairs("$Access\nacess\naccesing\n$accommodation\naccomodation\n".as_bytes());
+        assert_eq!(pairs, vec![
+            ("acess".to_owned(), "access".to_owned()),
+            ("accesing".to_owned(), "access".to_owned()),
+            ("accomodation".to_owned(), "accommodation".to_owned()),
+        ]);
+    }
+
+    #[test]
+    fn uniform_model_test() {
+        let model = ErrorModel::uniform();
+        assert_eq!(model.probability("speling", "spelling"), 1.0);
+        assert_eq!(model.probability("xyz", "abc"), 1.0);
+    }
+
+    #[test]
+    fn trained_model_prefers_seen_edits() {
+        let model = ErrorModel::from_pairs("teh -> the\nhte -> the\nadn -> and".as_bytes());
+        let seen = model.edit_probability(&Edit::Transpose('h', 'e'));
+        let unseen = model.edit_probability(&Edit::Delete('q'));
+        assert!(seen > unseen);
+    }
+
+    #[test]
+    fn trained_model_prefers_adjacent_keys() {
+        let model = ErrorModel::from_pairs("".as_bytes());
+        assert!(model.probability("wprd", "word") > model.probability("wzrd", "word"));
+    }
+}
@@ -0,0 +1,278 @@
+#![doc="
+    Type-ahead completion from the trained vocabulary. Words are stored in a trie, and every
+    node also records the highest count in its subtree, so the most frequent completions of a
+    prefix come out of a best-first search without visiting the whole subtree: a node can't
+    hold any word more frequent than its subtree maximum.
+
+    Fuzzy completion tolerates one typo (insertion, deletion, substitution or transposition of
+    adjacent characters) in the prefix. As in correction, completions of the prefix as typed
+    always come before completions of a prefix one edit away.
+
+    The prefix is normalized like the training corpus (text::normalize), so `The` completes to
+    the lowercase words the vocabulary holds.
+"]
+
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashSet};
+
+use table::CountTable;
+use text::normalize;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Completion<'a> {
+    pub word: &'a str,
+    pub count: usize,
+    /// Edits between the typed prefix and the word's prefix, 0 or 1
+    pub distance: usize,
+}
+
+#[derive(Default)]
+struct Node {
+    /// (character, child node), sorted by character
+    children: Vec<(char, usize)>,
+    /// The word ending here, if any, and its count
+    word: Option<(String, usize)>,
+    /// Highest count in this subtree
+    best: usize,
+}
+
+pub struct Trie {
+    nodes: Vec<Node>,
+    words: usize,
+}
+
+impl Trie {
+    pub fn new(table: &CountTable) -> Trie {
+        let mut trie = Trie { nodes: vec![Node::default()], words: 0 };
+        for (word, &count) in table {
+            trie.insert(word, count);
+        }
+        trie
+    }
+
+    pub fn len(&self) -> usize {
+        self.words
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.words == 0
+    }
+
+    /// Adds a word, or replaces its count
+    pub fn insert(&mut self, word: &str, count: usize) {
+        let mut path = vec![0];
+        for c in word.chars() {
+            let current = *path.last().expect("path starts at the root");
+            let next = match self.nodes[current].children.binary_search_by_key(&c, |&(c, _)| c) {
+                Ok(i) => self.nodes[current].children[i].1,
+                Err(i) => {
+                    let child = self.nodes.len();
+                    self.nodes.push(Node::default());
+                    self.nodes[current].children.insert(i, (c, child));
+                    child
+                }
+            };
+            path.push(next);
+        }
+
+        let end = *path.last().expect("path starts at the root");
+        if self.nodes[end].word.is_none() {
+            self.words += 1;
+        }
+        self.nodes[end].word = Some((word.to_owned(), count));
+        // Only the counts on the path changed
+        for &node in path.iter().rev() {
+            let own = self.nodes[node].word.as_ref().map_or(0, |&(_, count)| count);
+            let children = self.nodes[node].children.iter().map(|&(_, child)| self.nodes[child].best).max();
+            self.nodes[node].best = own.max(children.unwrap_or(0));
+        }
+    }
+
+    fn find(&self, prefix: &str) -> Option<usize> {
+        let mut current = 0;
+        for c in prefix.chars() {
+            current = self.child(current, c)?;
+        }
+        Some(current)
+    }
+
+    fn child(&self, node: usize, c: char) -> Option<usize> {
+        let children = &self.nodes[node].children;
+        children.binary_search_by_key(&c, |&(c, _)| c).ok().map(|i| children[i].1)
+    }
+
+    /// The `n` most frequent words starting with `prefix`, most frequent first (ties in
+    /// alphabetical order)
+    pub fn complete(&self, prefix: &str, n: usize) -> Vec<Completion<'_>> {
+        let prefix = &normalize(prefix);
+        let mut found = Vec::new();
+        if let Some(start) = self.find(prefix) {
+            self.best_first(&[(start, prefix.to_owned())], n, 0, &mut found);
+        }
+        found
+    }
+
+    /// Like `complete`, then filled up to `n` with completions of prefixes one edit away
+    pub fn complete_fuzzy(&self, prefix: &str, n: usize) -> Vec<Completion<'_>> {
+        let mut found = self.complete(prefix, n);
+        let typed: Vec<char> = normalize(prefix).chars().collect();
+        let mut starts = Vec::new();
+        self.one_edit(0, &typed, true, &mut String::new(), &mut starts);
+        starts.sort();
+        starts.dedup();
+        self.best_first(&starts, n, 1, &mut found);
+        found
+    }
+
+    /// Nodes reached by following `rest` from `node` with exactly one edit, if `edit` is
+    /// still available, with their paths
+    fn one_edit(&self, node: usize, rest: &[char], edit: bool, path: &mut String, found: &mut Vec<(usize, String)>) {
+        let first = match rest.first() {
+            Some(&first) => first,
+            None => {
+                if !edit {
+                    found.push((node, path.clone()));
+                }
+                return;
+            }
+        };
+        if let Some(child) = self.child(node, first) {
+            self.follow(child, first, &rest[1..], edit, path, found);
+        }
+        if !edit {
+            return;
+        }
+        // Deletion of a typed character
+        self.one_edit(node, &rest[1..], false, path, found);
+        for &(c, child) in &self.nodes[node].children {
+            // Substitution
+            if c != first {
+                self.follow(child, c, &rest[1..], false, path, found);
+            }
+            // Insertion of a missing character
+            self.follow(child, c, rest, false, path, found);
+        }
+        // Transposition
+        if rest.len() >= 2 && rest[0] != rest[1] {
+            if let Some(child) = self.child(node, rest[1]) {
+                if let Some(grandchild) = self.child(child, rest[0]) {
+                    path.push(rest[1]);
+                    self.follow(grandchild, rest[0], &rest[2..], false, path, found);
+                    path.pop();
+                }
+            }
+        }
+    }
+
+    /// `one_edit` from `child`, reached from the current node by `c`
+    fn follow(&self, child: usize, c: char, rest: &[char], edit: bool, path: &mut String,
+              found: &mut Vec<(usize, String)>) {
+        path.push(c);
+        self.one_edit(child, rest, edit, path, found);
+        path.pop();
+    }
+
+    /// Adds words under the `starts` nodes to `found`, most frequent first, until it has `n`
+    fn best_first<'a>(&'a self, starts: &[(usize, String)], n: usize, distance: usize,
+                      found: &mut Vec<Completion<'a>>) {
+        let mut seen: HashSet<&str> = found.iter().map(|c| c.word).collect();
+        // (count or subtree best, path in reverse order for alphabetical ties, whether it's a
+        // word rather than a subtree, node)
+        let mut pending: BinaryHeap<(usize, Reverse<String>, bool, usize)> = starts.iter()
+            .map(|(node, path)| (self.nodes[*node].best, Reverse(path.clone()), false, *node))
+            .collect();
+
+        while found.len() < n {
+            let (_, Reverse(path), is_word, node) = match pending.pop() {
+                Some(next) => next,
+                None => break,
+            };
+            let current = &self.nodes[node];
+            if is_word {
+                let (ref word, count) = *current.word.as_ref().expect("only words are queued as words");
+                if seen.insert(word) {
+                    found.push(Completion { word, count, distance });
+                }
+                continue;
+            }
+            if let Some((_, count)) = current.word {
+                pending.push((count, Reverse(path.clone()), true, node));
+            }
+            for &(c, child) in &current.children {
+                pending.push((self.nodes[child].best, Reverse(format!("{}{}", path, c)), false, child));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod complete_tests {
+    use super::{Completion, Trie};
+    use table::form_table;
+
+    fn trie() -> Trie {
+        Trie::new(&form_table("the the the the then there there they their help help hello held \
+                               hell hell hell hello hello hello hello world".as_bytes()))
+    }
+
+    fn words(completions: Vec<Completion<'_>>) -> Vec<&str> {
+        completions.into_iter().map(|c| c.word).collect()
+    }
+
+    #[test]
+    fn complete_by_frequency() {
+        let trie = trie();
+        assert_eq!(trie.len(), 10);
+        assert_eq!(words(trie.complete("the", 3)), vec!["the", "there", "their"]);
+        assert_eq!(words(trie.complete("hel", 10)), vec!["hello", "hell", "help", "held"]);
+        assert_eq!(trie.complete("wor", 5), vec![Completion { word: "world", count: 1, distance: 0 }]);
+        assert_eq!(words(trie.complete("", 2)), vec!["hello", "the"]);
+        assert!(trie.complete("x", 5).is_empty());
+        assert!(trie.complete("the", 0).is_empty());
+        // Typed prefixes are normalized like the vocabulary
+        assert_eq!(words(trie.complete("The", 3)), vec!["the", "there", "their"]);
+        assert_eq!(words(trie.complete_fuzzy("WRL", 1)), vec!["world"]);
+    }
+
+    #[test]
+    fn complete_matches_sorting_everything() {
+        let table = form_table("a ab abc abd b ba bab abc abd abd b b bab ac ac ac".as_bytes());
+        let trie = Trie::new(&table);
+        for prefix in &["", "a", "ab", "b", "ba", "c"] {
+            let mut expected: Vec<(&String, &usize)> = table.iter().filter(|(w, _)| w.starts_with(prefix)).collect();
+            expected.sort_by(|a, b| b.1.cmp(a.1).then_with(|| a.0.cmp(b.0)));
+            let expected: Vec<&str> = expected.iter().map(|(w, _)| w.as_str()).collect();
+            assert_eq!(words(trie.complete(prefix, 100)), expected, "prefix {}", prefix);
+        }
+    }
+
+    #[test]
+    fn fuzzy_tolerates_one_typo() {
+        let trie = trie();
+        // Substitution, transposition, deletion and insertion
+        assert_eq!(words(trie.complete_fuzzy("hwl", 2)), vec!["hello", "hell"]);
+        assert_eq!(words(trie.complete_fuzzy("hle", 1)), vec!["hello"]);
+        assert_eq!(words(trie.complete_fuzzy("wxor", 1)), vec!["world"]);
+        assert_eq!(words(trie.complete_fuzzy("wrl", 1)), vec!["world"]);
+        assert!(trie.complete_fuzzy("wxyz", 5).is_empty());
+    }
+
+    #[test]
+    fn fuzzy_ranks_exact_prefix_first() {
+        let trie = trie();
+        let found = trie.complete_fuzzy("hell", 4);
+        assert_eq!(found[0], Completion { word: "hello", count: 5, distance: 0 });
+        assert_eq!(found[1], Completion { word: "hell", count: 3, distance: 0 });
+        assert_eq!(words(found[2..].to_vec()), vec!["help", "held"]);
+        assert!(found[2..].iter().all(|c| c.distance == 1));
+    }
+
+    #[test]
+    fn insert_updates_counts() {
+        let mut trie = trie();
+        trie.insert("helium", 100);
+        trie.insert("hell", 0);
+        assert_eq!(trie.len(), 11);
+        assert_eq!(words(trie.complete("hel", 3)), vec!["helium", "hello", "help"]);
+    }
+}
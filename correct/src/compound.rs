@@ -0,0 +1,130 @@
+#![doc="
+    Words typed together (inthe) or apart (note book). Neither is reachable with single-word
+    edits, since the alphabet has no space in it.
+
+    Run-together words are segmented with dynamic programming over the unigram model: the best
+    split of the first i letters is the best split of some shorter prefix plus one known word,
+    maximizing the product of the words' probabilities. Each inserted space counts as one edit,
+    so a split into two words competes with the candidates one edit away.
+
+    Two words are joined when their concatenation is known and one of them isn't (to gether).
+    When both are known, only the bigram counts can tell `a part` from `apart`, so the join needs
+    a context model and happens when the corpus has seen the joined word more often than the
+    pair.
+"]
+
+use context::ContextModel;
+use corrector::Corrector;
+
+/// Longest word, in chars, tried as one piece of a segmentation
+pub const MAX_PIECE_CHARS: usize = 24;
+
+/// The most probable split of `word` into two or more known words
+pub fn segment(word: &str, corrector: &Corrector) -> Option<Vec<String>> {
+    let chars: Vec<char> = word.chars().collect();
+    // best[i] = (log probability, start of the last piece) for the first i chars
+    let mut best: Vec<Option<(f64, usize)>> = vec![None; chars.len() + 1];
+    best[0] = Some((0.0, 0));
+
+    for end in 1..=chars.len() {
+        for start in end.saturating_sub(MAX_PIECE_CHARS)..end {
+            let before = match best[start] {
+                Some((before, _)) => before,
+                None => continue,
+            };
+            let piece: String = chars[start..end].iter().collect();
+            if !corrector.is_known(&piece) {
+                continue;
+            }
+            let score = before + corrector.probability(&piece).ln();
+            if best[end].is_none_or(|(top, _)| score > top) {
+                best[end] = Some((score, start));
+            }
+        }
+    }
+
+    let mut pieces = Vec::new();
+    let mut end = chars.len();
+    while end > 0 {
+        let (_, start) = best[end]?;
+        pieces.push(chars[start..end].iter().collect());
+        end = start;
+    }
+    pieces.reverse();
+    if pieces.len() < 2 {
+        return None;
+    }
+    Some(pieces)
+}
+
+/// The single word `first` and `second` should be written as, if any. Both are lookup keys.
+pub fn join(first: &str, second: &str, corrector: &Corrector, context: Option<&ContextModel>) -> Option<String> {
+    let joined = format!("{}{}", first, second);
+    if !corrector.is_known(&joined) {
+        return None;
+    }
+    if !corrector.is_known(first) || !corrector.is_known(second) {
+        return Some(joined);
+    }
+    match context {
+        Some(context) if corrector.count(&joined) > context.bigram_count(first, second) => Some(joined),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod compound_tests {
+    use super::{join, segment};
+    use context::ContextModel;
+    use corrector::Corrector;
+    use table::{form_table, form_tables};
+
+    fn corrector() -> Corrector {
+        Corrector::new(form_table("in the park the man sat in the sun in a hat on the bench at noon".as_bytes()))
+    }
+
+    fn split(word: &str) -> Option<Vec<String>> {
+        segment(word, &corrector())
+    }
+
+    #[test]
+    fn segment_test() {
+        assert_eq!(split("inthe"), Some(vec!["in".to_owned(), "the".to_owned()]));
+        assert_eq!(split("inthepark"), Some(vec!["in".to_owned(), "the".to_owned(), "park".to_owned()]));
+        assert_eq!(split("themansat"), Some(vec!["the".to_owned(), "man".to_owned(), "sat".to_owned()]));
+    }
+
+    #[test]
+    fn segment_needs_known_pieces() {
+        assert_eq!(split("inthex"), None);
+        assert_eq!(split("park"), None);
+        assert_eq!(split(""), None);
+    }
+
+    #[test]
+    fn segment_prefers_probable_words() {
+        let corrector = Corrector::new(form_table("at at at one one one a a tone".as_bytes()));
+        assert_eq!(segment("atone", &corrector), Some(vec!["at".to_owned(), "one".to_owned()]));
+        let corrector = Corrector::new(form_table("at one a a a tone tone tone".as_bytes()));
+        assert_eq!(segment("atone", &corrector), Some(vec!["a".to_owned(), "tone".to_owned()]));
+    }
+
+    #[test]
+    fn join_with_an_unknown_part() {
+        let corrector = Corrector::new(form_table("we went together to the notebook store for a note and a book".as_bytes()));
+        assert_eq!(join("to", "gether", &corrector, None), Some("together".to_owned()));
+        assert_eq!(join("to", "the", &corrector, None), None);
+        assert_eq!(join("note", "book", &corrector, None), None);
+    }
+
+    #[test]
+    fn join_known_parts_needs_context() {
+        let (table, bigrams) = form_tables("my notebook and a note on a book. a part of it was apart, \
+                                            a part of a part".as_bytes());
+        let corrector = Corrector::new(table);
+        let context = ContextModel::new(bigrams);
+        assert_eq!(join("note", "book", &corrector, None), None);
+        assert_eq!(join("note", "book", &corrector, Some(&context)), Some("notebook".to_owned()));
+        assert_eq!(join("a", "part", &corrector, Some(&context)), None);
+    }
+}
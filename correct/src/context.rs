@@ -0,0 +1,161 @@
+#![doc="
+    Context-aware correction with a bigram language model. Candidates for a word are scored by
+    the error model times the stupid backoff probability of the word given its left neighbour
+    and of its right neighbour given the word:
+
+    ```text
+    S(w | p) = count(p w) / count(p)       if the bigram was seen
+             = BACKOFF * count(w) / N      otherwise
+    ```
+
+    Because the typed word itself competes with its neighbours, known words can be replaced
+    too (their / there), which single-word correction can't do. To keep well-spelled text
+    stable, a known word is kept with probability KEEP_PROBABILITY under the channel, and an
+    alternative only competes if the corpus has seen it next to one of the surrounding words.
+    Searching a known word's neighbours is slow, so it is only done when the corpus hasn't seen
+    the word itself next to both of its known neighbours; an attested word is kept.
+"]
+
+use corrector::{Candidate, Corrector};
+use table::BigramTable;
+
+/// Weight given to the unigram probability when a bigram was never seen
+pub const BACKOFF: f64 = 0.4;
+/// Prior that a known word was typed as intended
+pub const KEEP_PROBABILITY: f64 = 0.95;
+
+pub struct ContextModel {
+    bigrams: BigramTable,
+}
+
+impl ContextModel {
+    pub fn new(bigrams: BigramTable) -> ContextModel {
+        ContextModel { bigrams }
+    }
+
+    pub fn bigram_count(&self, first: &str, second: &str) -> usize {
+        *self.bigrams.get(&(first.to_owned(), second.to_owned())).unwrap_or(&0)
+    }
+
+    /// Stupid backoff score of `word` following `previous`. `word` may be a split, several words
+    /// separated by spaces.
+    pub fn score(&self, corrector: &Corrector, previous: Option<&str>, word: &str) -> f64 {
+        if let Some(previous) = previous {
+            let seen = self.bigram_count(previous, word);
+            if seen > 0 {
+                return seen as f64 / corrector.count(previous) as f64;
+            }
+            return BACKOFF * corrector.phrase_probability(word);
+        }
+        corrector.phrase_probability(word)
+    }
+
+    /// The best word to put between `previous` and `next`, if it isn't `word` itself. All
+    /// three are lookup keys (lowercased, see text::normalize).
+    pub fn choose(&self, corrector: &Corrector, previous: Option<&str>, word: &str,
+                  next: Option<&str>) -> Option<String> {
+        // Unknown neighbours tell us nothing
+        let previous = previous.filter(|p| corrector.is_known(p));
+        let next = next.filter(|n| corrector.is_known(n));
+
+        let known = corrector.is_known(word);
+        let candidates: Vec<Candidate> = if !known {
+            corrector.candidates(word)
+        } else if self.attested(word, previous, next) {
+            return None;
+        } else {
+            corrector.neighbours(word).into_iter()
+                .filter(|c| self.supported(&c.word, previous, next))
+                .collect()
+        };
+
+        let mut best: Option<(f64, String)> = None;
+        if known {
+            best = Some((KEEP_PROBABILITY * self.in_context(corrector, previous, word, next), word.to_owned()));
+        }
+
+        for candidate in candidates {
+            let mut channel = corrector.channel_probability(word, &candidate.word);
+            if known {
+                channel *= 1.0 - KEEP_PROBABILITY;
+            }
+            let score = channel * self.in_context(corrector, previous, &candidate.word, next);
+            // strictly greater, so ties keep the earlier (better ranked) candidate
+            if best.as_ref().is_none_or(|&(top, _)| score > top) {
+                best = Some((score, candidate.word));
+            }
+        }
+
+        best.map(|(_, w)| w).filter(|w| w != word)
+    }
+
+    fn in_context(&self, corrector: &Corrector, previous: Option<&str>, word: &str,
+                  next: Option<&str>) -> f64 {
+        let mut score = self.score(corrector, previous, word);
+        if let Some(next) = next {
+            score *= self.score(corrector, Some(word), next);
+        }
+        score
+    }
+
+    /// Whether the corpus has seen `word` next to each of the neighbours given. With none, no
+    /// alternative could be supported either.
+    fn attested(&self, word: &str, previous: Option<&str>, next: Option<&str>) -> bool {
+        previous.is_none_or(|p| self.bigram_count(p, word) > 0)
+            && next.is_none_or(|n| self.bigram_count(word, n) > 0)
+    }
+
+    fn supported(&self, word: &str, previous: Option<&str>, next: Option<&str>) -> bool {
+        previous.is_some_and(|p| self.bigram_count(p, word) > 0)
+            || next.is_some_and(|n| self.bigram_count(word, n) > 0)
+    }
+}
+
+#[cfg(test)]
+mod context_tests {
+    use super::ContextModel;
+    use corrector::Corrector;
+    use table::form_tables;
+
+    static CORPUS: &str = "They lost their keys. I put it over there today. \
+                           Their house is over there. We saw their keys there.";
+
+    fn models() -> (Corrector, ContextModel) {
+        let (table, bigrams) = form_tables(CORPUS.as_bytes());
+        (Corrector::new(table), ContextModel::new(bigrams))
+    }
+
+    #[test]
+    fn score_uses_bigram_when_seen() {
+        let (corrector, context) = models();
+        assert_eq!(context.score(&corrector, Some("lost"), "their"), 1.0);
+        assert!(context.score(&corrector, Some("lost"), "there") < 0.1);
+        assert_eq!(context.score(&corrector, None, "there"), corrector.probability("there"));
+    }
+
+    #[test]
+    fn fixes_real_word_error() {
+        let (corrector, context) = models();
+        assert_eq!(context.choose(&corrector, Some("lost"), "there", Some("keys")), Some("their".to_owned()));
+        assert_eq!(context.choose(&corrector, Some("over"), "their", Some("today")), Some("there".to_owned()));
+    }
+
+    #[test]
+    fn keeps_word_that_fits() {
+        let (corrector, context) = models();
+        assert_eq!(context.choose(&corrector, Some("over"), "there", None), None);
+        assert_eq!(context.choose(&corrector, Some("lost"), "their", Some("keys")), None);
+        assert_eq!(context.choose(&corrector, None, "house", None), None);
+        // Attested on both sides, so its neighbours aren't searched
+        assert!(context.attested("their", Some("lost"), Some("keys")));
+        assert!(!context.attested("there", Some("lost"), Some("keys")));
+        assert!(context.attested("house", None, None));
+    }
+
+    #[test]
+    fn context_picks_between_candidates() {
+        let (corrector, context) = models();
+        assert_eq!(context.choose(&corrector, Some("over"), "theer", None), Some("there".to_owned()));
+        assert_eq!(context.choose(&corrector, Some("lost"), "theer", None), Some("their".to_owned()));
+    }
+}
@@ -0,0 +1,658 @@
+#![doc="
+    Checking phase. Combines the count table (the language model, P(c)) with the error model
+    (the channel, P(w|c)) following Norvig's full noisy-channel model: a known word is its own
+    correction, otherwise known words one edit away are preferred over known words two edits
+    away, and within the closest tier candidates are ranked by P(c) * P(w|c).
+
+    Run-together words are also split into known words (see compound), each inserted space
+    counting as one edit, so a split needs no more spaces than the farthest tier has edits; the
+    split's P(c) is the product of its words' probabilities (phrase_probability).
+
+    With a phonetic index, known words that sound like the typed word join the closest tier
+    whatever their edit distance, scored by P(c) times the phonetic weight.
+
+    Candidates are found by generating every edit of the typed word (up to two), or with a
+    BK-tree over the vocabulary, which finds known words within any distance; the tiers are then
+    exact Damerau-Levenshtein distances.
+
+    The best candidate always wins its tier, however poor a guess it is, so every correction
+    also gets a confidence between 0 and 1: the best candidate's share of its tier's total
+    score, halved for every edit beyond the first. Below the minimum confidence (0 unless set),
+    `correct` abstains.
+
+    Words are looked up the way training counts them (text::normalize), so any capitalization of
+    a known word is accepted and `correct` writes its correction with the typed word's
+    capitalization. Exemptions are checked as typed, since an ignore pattern may depend on case.
+    Candidates are always lowercase lookup keys.
+"]
+
+use std::cmp::Ordering;
+use std::fs::File;
+
+use bktree::BkTree;
+use channel::{edit_distance, ErrorModel};
+use compound::segment;
+use edits::{edits_one_with, edits_two_from};
+use personal::{IgnoreList, PersonalDictionary};
+use phonetic::{Algorithm, PhoneticIndex};
+use table::{alphabet_of, CountTable};
+use text::{normalize, restore_case};
+
+/// A known word that could be what the user meant
+#[derive(Clone, Debug, PartialEq)]
+pub struct Candidate {
+    pub word: String,
+    pub distance: usize,
+    pub count: usize,
+    pub score: f64,
+}
+
+pub struct Corrector {
+    table: CountTable,
+    total: usize,
+    alphabet: Vec<char>,
+    errors: ErrorModel,
+    personal: PersonalDictionary,
+    ignore: IgnoreList,
+    phonetic: Option<(PhoneticIndex, f64)>,
+    /// BK-tree search and its maximum distance, instead of generating edits
+    bktree: Option<(BkTree, usize)>,
+    min_confidence: f64,
+}
+
+/// Farthest tier searched when generating edits
+pub const EDITS_MAX_DISTANCE: usize = 2;
+
+/// Factor applied to the confidence for every edit beyond the first
+pub const DISTANCE_CONFIDENCE: f64 = 0.5;
+
+impl Corrector {
+    /// Corrector where every edit is equally likely
+    pub fn new(table: CountTable) -> Corrector {
+        Corrector::with_error_model(table, ErrorModel::uniform())
+    }
+
+    /// Corrector set up from the files every front end takes: misspelling/correction pairs for
+    /// the error model, a personal dictionary, and ignore patterns added to the defaults
+    /// @return Err - "path: reason" for the first file that can't be loaded
+    pub fn from_files(table: CountTable, errors: Option<&str>, dict: Option<&str>, ignore: Option<&str>)
+            -> Result<Corrector, String> {
+        let errors = match errors {
+            Some(path) => ErrorModel::from_pairs(File::open(path).map_err(|e| format!("{}: {}", path, e))?),
+            None => ErrorModel::uniform(),
+        };
+        let mut corrector = Corrector::with_error_model(table, errors);
+        if let Some(path) = dict {
+            corrector.set_personal(PersonalDictionary::open(path).map_err(|e| format!("{}: {}", path, e))?);
+        }
+        if let Some(path) = ignore {
+            let mut list = IgnoreList::defaults();
+            File::open(path).map_err(|e| e.to_string())
+                .and_then(|f| list.add_from(f))
+                .map_err(|e| format!("{}: {}", path, e))?;
+            corrector.set_ignore(list);
+        }
+        Ok(corrector)
+    }
+
+    pub fn with_error_model(table: CountTable, errors: ErrorModel) -> Corrector {
+        let total = table.values().sum();
+        let alphabet = alphabet_of(&table);
+        Corrector {
+            table,
+            total,
+            alphabet,
+            errors,
+            personal: PersonalDictionary::new(),
+            ignore: IgnoreList::defaults(),
+            phonetic: None,
+            bktree: None,
+            min_confidence: 0.0,
+        }
+    }
+
+    pub fn set_personal(&mut self, personal: PersonalDictionary) {
+        self.personal = personal;
+    }
+
+    pub fn personal(&self) -> &PersonalDictionary {
+        &self.personal
+    }
+
+    pub fn personal_mut(&mut self) -> &mut PersonalDictionary {
+        &mut self.personal
+    }
+
+    pub fn set_ignore(&mut self, ignore: IgnoreList) {
+        self.ignore = ignore;
+    }
+
+    pub fn ignore_list(&self) -> &IgnoreList {
+        &self.ignore
+    }
+
+    /// Indexes the vocabulary by phonetic key, so sound-alikes become candidates with a score
+    /// of `weight` times their probability
+    pub fn set_phonetic(&mut self, algorithm: Algorithm, weight: f64) {
+        let index = PhoneticIndex::new(algorithm, &self.table);
+        self.set_phonetic_index(index, weight);
+    }
+
+    /// Like `set_phonetic`, with an index already built over this vocabulary, such as the one
+    /// stored in a model
+    pub fn set_phonetic_index(&mut self, index: PhoneticIndex, weight: f64) {
+        self.phonetic = Some((index, weight));
+    }
+
+    /// Whether the word is exempt from correction: in the personal dictionary or matching an
+    /// ignore pattern
+    pub fn is_exempt(&self, word: &str) -> bool {
+        self.personal.contains(word) || self.ignore.matches(word)
+    }
+
+    /// Whether the word, as typed, should be left as it is: exempt as it is, or known once
+    /// normalized like the training corpus
+    pub fn is_accepted(&self, word: &str) -> bool {
+        self.is_exempt(word) || self.is_known(&normalize(word))
+    }
+
+    /// Searches candidates with a BK-tree over the vocabulary, up to `max_distance` edits away
+    pub fn set_bktree(&mut self, max_distance: usize) {
+        self.bktree = Some((BkTree::new(self.table.keys()), max_distance));
+    }
+
+    /// Farthest tier of candidates searched
+    pub fn max_distance(&self) -> usize {
+        self.bktree.as_ref().map_or(EDITS_MAX_DISTANCE, |&(_, max)| max)
+    }
+
+    /// Confidence below which `correct` abstains
+    pub fn set_min_confidence(&mut self, min_confidence: f64) {
+        self.min_confidence = min_confidence;
+    }
+
+    pub fn min_confidence(&self) -> f64 {
+        self.min_confidence
+    }
+
+    pub fn table(&self) -> &CountTable {
+        &self.table
+    }
+
+    /// Letters used to generate candidates, taken from the training vocabulary
+    pub fn alphabet(&self) -> &[char] {
+        &self.alphabet
+    }
+
+    pub fn is_known(&self, word: &str) -> bool {
+        self.table.contains_key(word)
+    }
+
+    pub fn count(&self, word: &str) -> usize {
+        *self.table.get(word).unwrap_or(&0)
+    }
+
+    /// P(c), the relative frequency of a word in the training corpus
+    pub fn probability(&self, word: &str) -> f64 {
+        if self.total == 0 {
+            return 0.0;
+        }
+        self.count(word) as f64 / self.total as f64
+    }
+
+    /// P(c) of a candidate that may be several words separated by spaces (a split): the
+    /// product of their probabilities
+    pub fn phrase_probability(&self, phrase: &str) -> f64 {
+        phrase.split(' ').map(|w| self.probability(w)).product()
+    }
+
+    /// Known candidates from the closest non-empty distance tier, best first
+    pub fn candidates(&self, word: &str) -> Vec<Candidate> {
+        let key = normalize(word);
+        if self.is_accepted(word) {
+            return vec![self.candidate(&key, &key, 0)];
+        }
+
+        let word = &key;
+        let split = self.split(word);
+        for (i, mut found) in self.search(word, true).into_iter().enumerate() {
+            let distance = i + 1;
+            if distance == 1 {
+                self.merge_sound_alikes(word, &mut found);
+            }
+            merge_split(&mut found, split.as_ref(), distance);
+            if !found.is_empty() {
+                return found;
+            }
+        }
+        // A split into more words than the farthest tier allows is no better a guess than
+        // any other word that far away
+        Vec::new()
+    }
+
+    /// Every known word other than `word` itself within the farthest tier, closest first.
+    /// Unlike `candidates`, this doesn't stop at the first tier, so it also works for real-word
+    /// errors where the typed word is itself known.
+    pub fn neighbours(&self, word: &str) -> Vec<Candidate> {
+        self.tiers(&normalize(word)).into_iter().flatten().collect()
+    }
+
+    /// The neighbours of a lookup key, one tier per distance up to the farthest
+    fn tiers(&self, word: &str) -> Vec<Vec<Candidate>> {
+        // Known words are never split up (together is not to get her)
+        let split = if self.is_known(word) { None } else { self.split(word) };
+
+        let mut tiers: Vec<Vec<Candidate>> = Vec::new();
+        for (i, mut tier) in self.search(word, false).into_iter().enumerate() {
+            let distance = i + 1;
+            if distance == 1 {
+                self.merge_sound_alikes(word, &mut tier);
+            }
+            tier.retain(|c| tiers.iter().flatten().all(|f| f.word != c.word));
+            merge_split(&mut tier, split.as_ref(), distance);
+            tiers.push(tier);
+        }
+        tiers
+    }
+
+    /// Every known word within the farthest tier, the chosen correction first: the word itself
+    /// if it is known, then the closer tiers before the farther ones
+    pub fn suggestions(&self, word: &str) -> Vec<Candidate> {
+        self.suggestions_with_confidence(word).0
+    }
+
+    /// `suggestions` and `correct_with_confidence` together, searching the tiers only once
+    pub fn suggestions_with_confidence(&self, word: &str) -> (Vec<Candidate>, Option<(String, f64)>) {
+        let key = normalize(word);
+        if self.is_accepted(word) {
+            let mut found = vec![self.candidate(&key, &key, 0)];
+            found.extend(self.tiers(&key).into_iter().flatten());
+            return (found, Some((word.to_owned(), 1.0)));
+        }
+
+        let tiers = self.tiers(&key);
+        // The closest non-empty tier is what `candidates` returns
+        let best = tiers.iter().find(|tier| !tier.is_empty())
+            .map(|tier| (restore_case(word, &tier[0].word), confidence(tier)));
+        (tiers.into_iter().flatten().collect(), best)
+    }
+
+    /// P(typed | intended) according to the error model
+    pub fn channel_probability(&self, typed: &str, intended: &str) -> f64 {
+        self.errors.probability(typed, intended)
+    }
+
+    /// The most probable correction, if any known word is close enough and the corrector is
+    /// confident enough in it
+    pub fn correct(&self, word: &str) -> Option<String> {
+        self.correct_with_confidence(word)
+            .filter(|&(_, confidence)| confidence >= self.min_confidence)
+            .map(|(best, _)| best)
+    }
+
+    /// The most probable correction and the confidence in it, whatever the minimum confidence.
+    /// An accepted word is returned as typed.
+    pub fn correct_with_confidence(&self, word: &str) -> Option<(String, f64)> {
+        if self.is_accepted(word) {
+            return Some((word.to_owned(), 1.0));
+        }
+        let candidates = self.candidates(word);
+        let confidence = confidence(&candidates);
+        candidates.into_iter().next().map(|best| (restore_case(word, &best.word), confidence))
+    }
+
+    /// Known words other than `word` up to the farthest tier, found in one search and bucketed
+    /// by distance: tiers[d - 1] holds those exactly d edits away, best first. With
+    /// `closest_only`, the tiers after a non-empty one may be left empty, which saves generating
+    /// the edits of edits.
+    fn search(&self, word: &str, closest_only: bool) -> Vec<Vec<Candidate>> {
+        let max_distance = self.max_distance();
+        let mut tiers = vec![Vec::new(); max_distance];
+        if let Some((ref tree, _)) = self.bktree {
+            for (known, distance) in tree.find(word, max_distance) {
+                if distance > 0 {
+                    tiers[distance - 1].push(self.candidate(word, known, distance));
+                }
+            }
+            for tier in &mut tiers {
+                tier.sort_by(compare_candidates);
+            }
+            return tiers;
+        }
+
+        let ones = edits_one_with(word, &self.alphabet);
+        tiers[0] = self.rank(word, ones.iter(), 1);
+        if tiers[0].is_empty() || !closest_only {
+            let twos = edits_two_from(&ones, &self.alphabet);
+            tiers[1] = self.rank(word, twos.iter().filter(|w| !ones.contains(*w)), 2);
+        }
+        tiers
+    }
+
+    /// Adds the known words that sound like `word` to a tier, keeping the better score for
+    /// words already in it
+    fn merge_sound_alikes(&self, word: &str, found: &mut Vec<Candidate>) {
+        let (index, weight) = match self.phonetic {
+            Some((ref index, weight)) => (index, weight),
+            None => return,
+        };
+        for known in index.matches(word) {
+            if known == word {
+                continue;
+            }
+            let score = weight * self.probability(known);
+            match found.iter_mut().find(|c| &c.word == known) {
+                Some(candidate) => candidate.score = candidate.score.max(score),
+                None => found.push(Candidate {
+                    word: known.clone(),
+                    distance: edit_distance(word, known),
+                    count: self.count(known),
+                    score,
+                }),
+            }
+        }
+        found.sort_by(compare_candidates);
+    }
+
+    /// The best segmentation of `word` into known words, as a candidate
+    fn split(&self, word: &str) -> Option<Candidate> {
+        let pieces = segment(word, self)?;
+        let phrase = pieces.join(" ");
+        Some(Candidate {
+            distance: pieces.len() - 1,
+            count: pieces.iter().map(|p| self.count(p)).min().unwrap_or(0),
+            score: self.phrase_probability(&phrase) * self.errors.probability(word, &phrase),
+            word: phrase,
+        })
+    }
+
+    fn rank<'a, I>(&self, word: &str, edits: I, distance: usize) -> Vec<Candidate>
+        where I: Iterator<Item = &'a String>
+    {
+        let mut found: Vec<Candidate> = edits
+            .filter(|w| w.as_str() != word && self.is_known(w))
+            .map(|w| self.candidate(word, w, distance))
+            .collect();
+
+        found.sort_by(compare_candidates);
+        found
+    }
+
+    fn candidate(&self, word: &str, known: &str, distance: usize) -> Candidate {
+        Candidate {
+            word: known.to_owned(),
+            distance,
+            count: self.count(known),
+            score: self.probability(known) * self.errors.probability(word, known),
+        }
+    }
+}
+
+/// Adds the split to a tier of candidates if it is that many edits away
+fn merge_split(found: &mut Vec<Candidate>, split: Option<&Candidate>, distance: usize) {
+    if let Some(split) = split.filter(|s| s.distance == distance) {
+        found.push(split.clone());
+        found.sort_by(compare_candidates);
+    }
+}
+
+/// How sure we can be that the first of `candidates` (one tier, best first) is meant, from 0
+/// to 1: its share of the tier's total score, times DISTANCE_CONFIDENCE for every edit beyond
+/// the first. 0 without candidates.
+pub fn confidence(candidates: &[Candidate]) -> f64 {
+    let best = match candidates.first() {
+        Some(best) => best,
+        None => return 0.0,
+    };
+    let total: f64 = candidates.iter().map(|c| c.score).sum();
+    let share = if total > 0.0 { best.score / total } else { 1.0 / candidates.len() as f64 };
+    share * DISTANCE_CONFIDENCE.powi(best.distance.saturating_sub(1) as i32)
+}
+
+/// Highest score first, ties broken alphabetically so output is deterministic
+pub fn compare_candidates(a: &Candidate, b: &Candidate) -> Ordering {
+    b.score.partial_cmp(&a.score).unwrap_or(Ordering::Equal)
+        .then_with(|| a.word.cmp(&b.word))
+}
+
+#[cfg(test)]
+mod corrector_tests {
+    use super::{confidence, Corrector};
+    use channel::ErrorModel;
+    use personal::IgnoreList;
+    use phonetic::Algorithm;
+    use table::{form_table, CountTable};
+
+    fn table(words: &[(&str, usize)]) -> CountTable {
+        words.iter().map(|&(w, c)| (w.to_owned(), c)).collect()
+    }
+
+    #[test]
+    fn confidence_test() {
+        let corrector = Corrector::new(table(&[("hello", 3), ("hell", 1), ("world", 1), ("wizard", 1)]));
+        // A known word, and a single candidate one edit away, are certain
+        assert_eq!(corrector.correct_with_confidence("hello"), Some(("hello".to_owned(), 1.0)));
+        assert_eq!(corrector.correct_with_confidence("wrld"), Some(("world".to_owned(), 1.0)));
+        // Shared with an alternative in the same tier
+        assert_eq!(corrector.correct_with_confidence("helo"), Some(("hello".to_owned(), 0.75)));
+        // Alone, but two edits away
+        assert_eq!(corrector.correct_with_confidence("wizrdd"), Some(("wizard".to_owned(), 0.5)));
+        assert_eq!(corrector.correct_with_confidence("zzzzzzz"), None);
+        assert_eq!(confidence(&[]), 0.0);
+    }
+
+    #[test]
+    fn correct_abstains_below_min_confidence() {
+        let mut corrector = Corrector::new(table(&[("hello", 3), ("hell", 1), ("world", 1), ("wizard", 1)]));
+        assert_eq!(corrector.min_confidence(), 0.0);
+        corrector.set_min_confidence(0.6);
+        assert_eq!(corrector.correct("helo"), Some("hello".to_owned()));
+        assert_eq!(corrector.correct("wizrdd"), None);
+        corrector.set_min_confidence(0.8);
+        assert_eq!(corrector.correct("helo"), None);
+        assert_eq!(corrector.correct("hello"), Some("hello".to_owned()));
+        assert_eq!(corrector.correct_with_confidence("helo"), Some(("hello".to_owned(), 0.75)));
+    }
+
+    #[test]
+    fn known_word_is_kept() {
+        let corrector = Corrector::new(table(&[("hello", 1), ("hell", 5)]));
+        assert_eq!(corrector.correct("hello"), Some("hello".to_owned()));
+        assert_eq!(corrector.candidates("hello")[0].distance, 0);
+    }
+
+    #[test]
+    fn distance_one_beats_frequent_distance_two() {
+        let corrector = Corrector::new(table(&[("hello", 1), ("jelly", 500)]));
+        assert_eq!(corrector.correct("hellp"), Some("hello".to_owned()));
+        assert_eq!(corrector.candidates("hellp").len(), 1);
+    }
+
+    #[test]
+    fn frequency_ranks_within_tier() {
+        let corrector = Corrector::new(table(&[("then", 2), ("than", 9)]));
+        assert_eq!(corrector.correct("thcn"), Some("than".to_owned()));
+    }
+
+    #[test]
+    fn correct_keeps_case() {
+        let corrector = Corrector::new(table(&[("hello", 3), ("dont", 1)]));
+        assert_eq!(corrector.correct("Hello"), Some("Hello".to_owned()));
+        assert_eq!(corrector.correct("Helo"), Some("Hello".to_owned()));
+        assert_eq!(corrector.correct("HELO"), Some("HELLO".to_owned()));
+        assert_eq!(corrector.correct("Don't"), Some("Don't".to_owned()));
+        assert_eq!(corrector.suggestions("Helo")[0].word, "hello");
+    }
+
+    #[test]
+    fn from_files_reports_the_missing_file() {
+        let error = Corrector::from_files(table(&[("hello", 1)]), None, None, Some("/nonexistent/ignore.txt"));
+        assert!(error.err().unwrap().starts_with("/nonexistent/ignore.txt: "));
+        let corrector = Corrector::from_files(table(&[("hello", 1)]), None, None, None).unwrap();
+        assert_eq!(corrector.correct("helo"), Some("hello".to_owned()));
+    }
+
+    #[test]
+    fn nothing_close_enough() {
+        let corrector = Corrector::new(table(&[("hello", 1)]));
+        assert_eq!(corrector.correct("zzzzzzz"), None);
+        assert!(corrector.candidates("zzzzzzz").is_empty());
+    }
+
+    #[test]
+    fn neighbours_of_known_word() {
+        let corrector = Corrector::new(table(&[("there", 3), ("their", 2), ("three", 1), ("the", 9)]));
+        let words: Vec<(String, usize)> = corrector.neighbours("there").into_iter()
+            .map(|c| (c.word, c.distance))
+            .collect();
+        assert_eq!(words, vec![
+            ("three".to_owned(), 1),
+            ("the".to_owned(), 2),
+            ("their".to_owned(), 2),
+        ]);
+    }
+
+    #[test]
+    fn suggestions_lead_with_correction() {
+        let corrector = Corrector::new(table(&[("there", 3), ("their", 2), ("three", 1), ("the", 9)]));
+        let words: Vec<String> = corrector.suggestions("there").into_iter().map(|c| c.word).collect();
+        assert_eq!(words, vec!["there", "three", "the", "their"]);
+
+        let suggestions = corrector.suggestions("thre");
+        assert_eq!(Some(suggestions[0].word.clone()), corrector.correct("thre"));
+        assert!(suggestions.iter().all(|c| c.word != "thre"));
+    }
+
+    #[test]
+    fn suggestions_with_confidence_agree() {
+        let mut corrector = Corrector::new(table(&[("hello", 3), ("hell", 1), ("world", 1), ("wizard", 1)]));
+        corrector.set_phonetic(Algorithm::Metaphone, 0.5);
+        for word in &["hello", "helo", "wrld", "wizrdd", "zzzzzzz"] {
+            let (suggestions, best) = corrector.suggestions_with_confidence(word);
+            assert_eq!(suggestions, corrector.suggestions(word));
+            assert_eq!(best, corrector.correct_with_confidence(word));
+        }
+    }
+
+    #[test]
+    fn personal_and_ignored_words_are_accepted() {
+        let mut corrector = Corrector::new(table(&[("kubernetes", 1), ("hash", 5), ("map", 5)]));
+        assert_eq!(corrector.correct("kubernetis"), Some("kubernetes".to_owned()));
+        assert_eq!(corrector.correct("HashMap"), Some("HashMap".to_owned()));
+
+        corrector.personal_mut().add("kubernetis").unwrap();
+        assert!(corrector.is_accepted("Kubernetis"));
+        assert_eq!(corrector.correct("kubernetis"), Some("kubernetis".to_owned()));
+        assert_eq!(corrector.suggestions("kubernetis")[0].word, "kubernetis");
+
+        corrector.set_ignore(IgnoreList::new());
+        assert!(!corrector.is_accepted("HashMap"));
+    }
+
+    #[test]
+    fn accented_words() {
+        let corrector = Corrector::new(form_table("Un café crème, déjà vu. Die Straße".as_bytes()));
+        assert_eq!(corrector.correct("cafe"), Some("café".to_owned()));
+        assert_eq!(corrector.correct("creme"), Some("crème".to_owned()));
+        assert_eq!(corrector.correct("deja"), Some("déjà".to_owned()));
+        assert_eq!(corrector.correct("strasse"), Some("straße".to_owned()));
+        assert_eq!(corrector.correct("caféé"), Some("café".to_owned()));
+    }
+
+    #[test]
+    fn error_model_breaks_ties() {
+        let words = table(&[("the", 1), ("tie", 1)]);
+        let uniform = Corrector::new(words.clone());
+        assert_eq!(uniform.correct("tye"), Some("the".to_owned()));
+
+        let errors = ErrorModel::from_pairs("tye -> tie\nlye -> lie\ndye -> die".as_bytes());
+        let weighted = Corrector::with_error_model(words, errors);
+        assert_eq!(weighted.correct("tye"), Some("tie".to_owned()));
+    }
+
+    #[test]
+    fn run_together_words_are_split() {
+        let corrector = Corrector::new(form_table("in the park the man sat in the sun with a hat".as_bytes()));
+        assert_eq!(corrector.correct("inthe"), Some("in the".to_owned()));
+        assert_eq!(corrector.correct("inthepark"), Some("in the park".to_owned()));
+        // five spaces are five edits, beyond the farthest tier unless the search goes that far
+        assert_eq!(corrector.correct("themansatinthesun"), None);
+        let mut far = Corrector::new(form_table("in the park the man sat in the sun with a hat".as_bytes()));
+        far.set_bktree(5);
+        assert_eq!(far.correct("themansatinthesun"), Some("the man sat in the sun".to_owned()));
+
+        let split = &corrector.candidates("inthe")[0];
+        assert_eq!((split.distance, split.count), (1, 2));
+        assert_eq!(split.score, corrector.probability("in") * corrector.probability("the"));
+        assert!(corrector.neighbours("park").iter().all(|c| !c.word.contains(' ')));
+    }
+
+    #[test]
+    fn splits_stay_within_max_distance() {
+        let corrector = Corrector::new(form_table("i saw a cat and i had a nap".as_bytes()));
+        assert_eq!(corrector.correct("hadcat"), Some("had cat".to_owned()));
+        assert_eq!(corrector.correct("iaiaiaiaia"), None);
+        assert!(corrector.suggestions("iaiaiaiaia").is_empty());
+        assert_eq!(corrector.phrase_probability("i a"), corrector.probability("i") * corrector.probability("a"));
+        assert_eq!(corrector.probability("i a"), 0.0);
+    }
+
+    #[test]
+    fn split_competes_within_its_tier() {
+        let corrector = Corrector::new(table(&[("in", 50), ("the", 50), ("inthem", 1)]));
+        assert_eq!(corrector.correct("inthe"), Some("in the".to_owned()));
+        let corrector = Corrector::new(table(&[("in", 5), ("the", 5), ("inthem", 1000)]));
+        assert_eq!(corrector.correct("inthe"), Some("inthem".to_owned()));
+    }
+
+    #[test]
+    fn sound_alikes_join_the_first_tier() {
+        let words = table(&[("phone", 5), ("one", 3), ("night", 4), ("note", 2), ("kite", 1)]);
+        let plain = Corrector::new(words.clone());
+        assert_eq!(plain.correct("fone"), Some("one".to_owned()));
+        assert_eq!(plain.correct("nite"), Some("note".to_owned()));
+
+        let mut phonetic = Corrector::new(words);
+        phonetic.set_phonetic(Algorithm::Metaphone, 1.0);
+        assert_eq!(phonetic.correct("fone"), Some("phone".to_owned()));
+        assert_eq!(phonetic.correct("nite"), Some("night".to_owned()));
+        let night = phonetic.candidates("nite").into_iter().find(|c| c.word == "night").unwrap();
+        assert_eq!(night.distance, 3);
+        assert!(phonetic.neighbours("nite").iter().any(|c| c.word == "night"));
+    }
+
+    #[test]
+    fn phonetic_weight_scales_sound_alikes() {
+        let mut corrector = Corrector::new(table(&[("phone", 5), ("one", 3)]));
+        corrector.set_phonetic(Algorithm::Metaphone, 0.5);
+        assert_eq!(corrector.correct("fone"), Some("one".to_owned()));
+        corrector.set_phonetic(Algorithm::Soundex, 10.0);
+        assert_eq!(corrector.correct("fone"), Some("one".to_owned()));
+    }
+
+    #[test]
+    fn bktree_search_matches_edits() {
+        let words = form_table("the quick brown fox jumps over the lazy dog then there their three".as_bytes());
+        let edits = Corrector::new(words.clone());
+        let mut tree = Corrector::new(words);
+        tree.set_bktree(2);
+        assert_eq!(tree.max_distance(), 2);
+        for word in &["teh", "qiuck", "thre", "lzy", "fxo", "zzzzzz", "the"] {
+            assert_eq!(tree.candidates(word), edits.candidates(word), "{}", word);
+        }
+        for word in &["there", "thre", "lzy"] {
+            assert_eq!(tree.neighbours(word), edits.neighbours(word), "{}", word);
+        }
+    }
+
+    #[test]
+    fn bktree_search_goes_farther() {
+        let mut corrector = Corrector::new(table(&[("necessary", 3), ("accommodate", 2)]));
+        assert_eq!(corrector.correct("nesesarry"), None);
+        corrector.set_bktree(3);
+        assert_eq!(corrector.correct("nesesarry"), Some("necessary".to_owned()));
+        assert_eq!(corrector.candidates("nesesarry")[0].distance, 3);
+        assert_eq!(corrector.correct("acomodat"), Some("accommodate".to_owned()));
+        assert_eq!(corrector.correct("zzzzzzzzz"), None);
+    }
+}
@@ -0,0 +1,222 @@
+#![doc="
+    Candidate generation. Produces every string reachable from a word by one (or two) small
+    edits: a deletion, a replacement, an insertion or a transposition of adjacent letters.
+    Replacements and insertions draw from an alphabet, which is English by default but is
+    normally derived from the training corpus (see table::alphabet_of).
+"]
+
+use std::collections::HashSet;
+
+pub type Edits = HashSet<String>;
+static ALPHABETS : &str = "abcdefghijklmnopqrstuvwxyz";
+
+/// The English alphabet, used when no alphabet is derived from a corpus
+pub fn default_alphabet() -> Vec<char> {
+    ALPHABETS.chars().collect()
+}
+
+pub fn edits_two(word: &str) -> Edits {
+    edits_two_with(word, &default_alphabet())
+}
+
+pub fn edits_one(word: &str) -> Edits {
+    edits_one_with(word, &default_alphabet())
+}
+
+pub fn edits_two_with(word: &str, alphabet: &[char]) -> Edits {
+    edits_two_from(&edits_one_with(word, alphabet), alphabet)
+}
+
+/// Every edit of the given single edits, for callers that already have them
+pub fn edits_two_from(edit_ones: &Edits, alphabet: &[char]) -> Edits {
+    let mut edits = Edits::new();
+
+    for edit_one_word in edit_ones.iter() {
+        edits.extend(edits_one_with(edit_one_word, alphabet));
+    }
+    edits
+}
+
+pub fn edits_one_with(word: &str, alphabet: &[char]) -> Edits {
+    let characters: Vec<char> = word.chars().collect();
+    let mut edits = Edits::new();
+
+    deletions(&characters, &mut edits);
+    replacements(&characters, alphabet, &mut edits);
+    insertions(&characters, alphabet, &mut edits);
+    transpositions(&characters, &mut edits);
+    edits
+}
+
+pub fn find_deletions(word: String) -> Edits {
+    let mut edits = Edits::new();
+    deletions(&word.chars().collect::<Vec<char>>(), &mut edits);
+    edits
+}
+
+pub fn find_replacements(word: String) -> Edits {
+    let mut edits = Edits::new();
+    replacements(&word.chars().collect::<Vec<char>>(), &default_alphabet(), &mut edits);
+    edits
+}
+
+pub fn find_insertions(word: String) -> Edits {
+    let mut edits = Edits::new();
+    insertions(&word.chars().collect::<Vec<char>>(), &default_alphabet(), &mut edits);
+    edits
+}
+
+pub fn find_transpositions(word: String) -> Edits {
+    let mut edits = Edits::new();
+    transpositions(&word.chars().collect::<Vec<char>>(), &mut edits);
+    edits
+}
+
+// The helpers below work on chars rather than bytes, so multi-byte letters (é, ß, ñ) are
+// edited as a whole instead of being split in the middle.
+
+fn deletions(characters: &[char], edits: &mut Edits) {
+    if characters.len() <= 1 {
+        return
+    }
+
+    for i in 0..characters.len() {
+        let deleted: String = characters[..i].iter().chain(&characters[i + 1..]).collect();
+        edits.insert(deleted);
+    }
+}
+
+fn replacements(characters: &[char], alphabet: &[char], edits: &mut Edits) {
+    for i in 0..characters.len() {
+        for c in alphabet {
+            let replaced: String = characters[..i].iter()
+                .chain(Some(c))
+                .chain(&characters[i + 1..])
+                .collect();
+            edits.insert(replaced);
+        }
+    }
+}
+
+fn insertions(characters: &[char], alphabet: &[char], edits: &mut Edits) {
+    for i in 0..characters.len() + 1 {
+        for c in alphabet {
+            let inserted: String = characters[..i].iter()
+                .chain(Some(c))
+                .chain(&characters[i..])
+                .collect();
+            edits.insert(inserted);
+        }
+    }
+}
+
+fn transpositions(characters: &[char], edits: &mut Edits) {
+    for i in 0..characters.len().saturating_sub(1) {
+        let mut transposed = characters.to_vec();
+        transposed.swap(i, i + 1);
+        edits.insert(transposed.into_iter().collect());
+    }
+}
+
+#[cfg(test)]
+mod edits_test {
+    use super::{find_transpositions, find_deletions, find_replacements, find_insertions, edits_one, edits_two,
+                edits_one_with};
+
+    #[test]
+    fn find_transpositions_test() {
+        let trans_1 = find_transpositions("ab".to_owned());
+        assert!(trans_1.contains("ba"));
+
+        let trans_2 = find_transpositions("abc".to_owned());
+        assert!(trans_2.contains("bac"));
+        assert!(trans_2.contains("acb"));
+        assert!(!trans_2.contains("abcd"));
+    }
+
+    #[test]
+    fn find_deletions_test() {
+        let dels = find_deletions("ab".to_owned());
+        assert!(dels.contains("b"));
+        assert!(dels.contains("a"));
+        assert!(!dels.contains("ab"));
+
+        let dels2 = find_deletions("abcd".to_owned());
+        assert!(dels2.contains("abc"));
+        assert!(dels2.contains("bcd"));
+        assert!(dels2.contains("acd"));
+        assert!(dels2.contains("abd"));
+
+    }
+
+    #[test]
+    fn find_replacements_test() {
+        let reps = find_replacements("abc".to_owned());
+        assert!(reps.contains("abd"));
+        assert!(reps.contains("dbc"));
+        assert!(reps.contains("acc"));
+
+        assert!(!reps.contains("accd"));
+        assert!(!reps.contains("cd"));
+        assert!(!reps.contains("bcd"));
+        assert!(!reps.contains("acd"));
+    }
+
+
+    #[test]
+    fn find_insertions_test() {
+        let ins = find_insertions("ab".to_owned());
+        assert!(ins.contains("abc"));
+        assert!(ins.contains("abb"));
+        assert!(ins.contains("abd"));
+
+        assert!(!ins.contains("acc"));
+        assert!(!ins.contains("abcd"));
+    }
+
+    #[test]
+    fn edit1_test() {
+        let edit1s = edits_one("abcd");
+        assert!(edit1s.contains("abd"));
+        assert!(edit1s.contains("abcde"));
+        assert!(edit1s.contains("abcz"));
+        assert!(edit1s.contains("bacd"));
+
+        assert!(!edit1s.contains("adzcd"));
+        assert!(!edit1s.contains("aabed"));
+        assert!(!edit1s.contains("cccd"));
+        assert!(!edit1s.contains("cd"));
+    }
+
+    #[test]
+    fn edit2_test() {
+        let edit2s = edits_two("abcd");
+        assert!(edit2s.contains("aabcd"));
+        assert!(edit2s.contains("aabed"));
+        assert!(edit2s.contains("badc"));
+        assert!(edit2s.contains("abcdef"));
+        assert!(edit2s.contains("cd"));
+
+        assert!(!edit2s.contains("d"));
+        assert!(!edit2s.contains("abcdefg"));
+        assert!(!edit2s.contains("cqfg"));
+    }
+
+    #[test]
+    fn multi_byte_test() {
+        assert!(find_deletions("café".to_owned()).contains("caf"));
+        assert!(find_transpositions("café".to_owned()).contains("caéf"));
+        assert!(find_insertions("über".to_owned()).contains("überr"));
+        assert!(find_replacements("straße".to_owned()).contains("strase"));
+    }
+
+    #[test]
+    fn edits_with_alphabet_test() {
+        let alphabet = vec!['é', 'ß', 'e'];
+        let edits = edits_one_with("cafe", &alphabet);
+        assert!(edits.contains("café"));
+        assert!(edits.contains("cafeß"));
+        assert!(!edits.contains("cafa"));
+        assert!(edits.contains("acfe"));
+    }
+}
@@ -0,0 +1,173 @@
+#![doc="
+    Measures correction quality on a test set of (misspelling, correction) pairs, such as
+    Norvig's spell-testset files or the Birkbeck corpus, so model changes can be compared.
+
+    Reported:
+        - accuracy: the best candidate is the expected correction
+        - recall: the expected correction is among the top suggestions
+        - unknown rate: the expected correction isn't in the dictionary at all, so no change to
+          ranking could have found it
+        - accuracy by the edit distance between the misspelling and its correction
+        - average time per correction
+        - every failure, with what the corrector picked instead
+"]
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::time::{Duration, Instant};
+
+use channel::edit_distance;
+use corrector::Corrector;
+
+/// A pair the corrector got wrong
+#[derive(Clone, Debug, PartialEq)]
+pub struct Failure {
+    pub typed: String,
+    pub expected: String,
+    pub got: Option<String>,
+    pub known: bool,
+}
+
+#[derive(Debug, Default)]
+pub struct Report {
+    pub total: usize,
+    pub correct: usize,
+    /// Expected correction within the top `suggestions` candidates
+    pub recalled: usize,
+    pub suggestions: usize,
+    /// Expected correction not in the dictionary
+    pub unknown: usize,
+    /// distance -> (correct, total)
+    pub by_distance: BTreeMap<usize, (usize, usize)>,
+    pub elapsed: Duration,
+    pub failures: Vec<Failure>,
+}
+
+impl Report {
+    pub fn accuracy(&self) -> f64 {
+        ratio(self.correct, self.total)
+    }
+
+    pub fn recall(&self) -> f64 {
+        ratio(self.recalled, self.total)
+    }
+
+    pub fn unknown_rate(&self) -> f64 {
+        ratio(self.unknown, self.total)
+    }
+
+    /// Average time spent in `Corrector::correct`
+    pub fn average_latency(&self) -> Duration {
+        if self.total == 0 {
+            Duration::from_secs(0)
+        } else {
+            self.elapsed / self.total as u32
+        }
+    }
+}
+
+fn ratio(part: usize, whole: usize) -> f64 {
+    if whole == 0 { 0.0 } else { part as f64 / whole as f64 }
+}
+
+/// Corrects every misspelling and compares the result with the expected correction
+pub fn evaluate(pairs: &[(String, String)], corrector: &Corrector, suggestions: usize) -> Report {
+    let mut report = Report { suggestions, ..Report::default() };
+
+    for (typed, expected) in pairs {
+        let start = Instant::now();
+        let got = corrector.correct(typed);
+        report.elapsed += start.elapsed();
+
+        let known = corrector.is_known(expected);
+        let right = got.as_ref() == Some(expected);
+        let recalled = right || corrector.suggestions(typed).iter().take(suggestions).any(|c| &c.word == expected);
+
+        report.total += 1;
+        if right {
+            report.correct += 1;
+        }
+        if recalled {
+            report.recalled += 1;
+        }
+        if !known {
+            report.unknown += 1;
+        }
+        let bucket = report.by_distance.entry(edit_distance(typed, expected)).or_insert((0, 0));
+        bucket.1 += 1;
+        if right {
+            bucket.0 += 1;
+        } else {
+            report.failures.push(Failure { typed: typed.clone(), expected: expected.clone(), got, known });
+        }
+    }
+    report
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "pairs:         {}", self.total)?;
+        writeln!(f, "accuracy:      {:.1}% ({} of {})", 100.0 * self.accuracy(), self.correct, self.total)?;
+        writeln!(f, "recall@{}:      {:.1}%", self.suggestions, 100.0 * self.recall())?;
+        writeln!(f, "unknown rate:  {:.1}% ({} expected words not in the dictionary)",
+                 100.0 * self.unknown_rate(), self.unknown)?;
+        writeln!(f, "avg latency:   {:.3} ms", self.average_latency().as_secs_f64() * 1000.0)?;
+        writeln!(f, "by distance:")?;
+        for (distance, &(correct, total)) in &self.by_distance {
+            writeln!(f, "  {:>2}: {:.1}% ({} of {})", distance, 100.0 * ratio(correct, total), correct, total)?;
+        }
+        writeln!(f, "failures:")?;
+        for failure in &self.failures {
+            writeln!(f, "  {} -> {} (expected {}{})", failure.typed, failure.got.as_ref().map_or("-", |g| g.as_str()),
+                     failure.expected, if failure.known { "" } else { ", unknown" })?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod eval_tests {
+    use super::{evaluate, Failure};
+    use channel::read_pairs;
+    use corrector::Corrector;
+    use table::form_table;
+
+    fn corrector() -> Corrector {
+        Corrector::new(form_table("the the the quick brown fox then".as_bytes()))
+    }
+
+    #[test]
+    fn evaluate_test() {
+        let pairs = read_pairs("teh -> the\nquikc -> quick\nthn -> then\nfxo -> fix\n".as_bytes());
+        let report = evaluate(&pairs, &corrector(), 5);
+
+        assert_eq!(report.total, 4);
+        assert_eq!(report.correct, 2);
+        assert_eq!(report.recalled, 3);
+        assert_eq!(report.unknown, 1);
+        assert_eq!(report.accuracy(), 0.5);
+        assert_eq!(report.by_distance.get(&1), Some(&(2, 3)));
+        assert_eq!(report.by_distance.get(&2), Some(&(0, 1)));
+        assert_eq!(report.failures, vec![
+            Failure { typed: "thn".to_owned(), expected: "then".to_owned(), got: Some("the".to_owned()), known: true },
+            Failure { typed: "fxo".to_owned(), expected: "fix".to_owned(), got: Some("fox".to_owned()), known: false },
+        ]);
+    }
+
+    #[test]
+    fn report_display() {
+        let pairs = read_pairs("teh -> the\nfxo -> fix\n".as_bytes());
+        let text = evaluate(&pairs, &corrector(), 5).to_string();
+        assert!(text.contains("accuracy:      50.0% (1 of 2)"));
+        assert!(text.contains("unknown rate:  50.0%"));
+        assert!(text.contains("   1: 100.0% (1 of 1)\n"));
+        assert!(text.contains("  fxo -> fox (expected fix, unknown)\n"));
+    }
+
+    #[test]
+    fn empty_test_set() {
+        let report = evaluate(&[], &corrector(), 5);
+        assert_eq!(report.accuracy(), 0.0);
+        assert_eq!(report.average_latency().as_secs(), 0);
+    }
+}
@@ -0,0 +1,455 @@
+#![doc="
+    Hunspell dictionaries as a vocabulary source. A Hunspell dictionary is a word list (.dic) of
+    stems with affix flags, plus an affix file (.aff) with the prefix and suffix rules each flag
+    stands for, so `work/AB` means work, worked, rework and reworked. Expanding every stem gives
+    the inflected forms a training corpus may never contain.
+
+    The expanded words are merged into the corpus counts: words the corpus has keep their
+    counts, the others get DICTIONARY_COUNT, so they are known but rank like a word the corpus
+    saw once, below anything it saw more often.
+
+    Supported from the affix file:
+
+    ```text
+    SET            UTF-8 or ISO8859-1 (for both files)
+    FLAG           char (default), UTF-8, long (two characters) or num (comma separated)
+    AF             flag aliases, referred to by number
+    PFX, SFX       rules with strip, add, continuation flags and condition; cross products of
+                   a prefix and a suffix; continuation flags applied once (twofold affixes)
+    NEEDAFFIX      stems that aren't words without an affix
+    FORBIDDENWORD  entries that are left out
+    ```
+
+    Compounding, replacement tables and morphology are ignored; they don't change which words
+    exist. Words are normalized like the training corpus (text::normalize): lowercased, with
+    apostrophes removed.
+"]
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+use table::CountTable;
+use text::normalize;
+
+/// Count given to dictionary words the corpus never saw
+pub const DICTIONARY_COUNT: usize = 1;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum FlagType {
+    Char,
+    Long,
+    Num,
+}
+
+#[derive(Debug)]
+enum CharClass {
+    Any,
+    /// (negated, chars)
+    Set(bool, Vec<char>),
+}
+
+impl CharClass {
+    fn matches(&self, c: char) -> bool {
+        match *self {
+            CharClass::Any => true,
+            CharClass::Set(negated, ref chars) => chars.contains(&c) != negated,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Affix {
+    strip: String,
+    add: String,
+    continuation: Vec<String>,
+    condition: Vec<CharClass>,
+}
+
+#[derive(Debug)]
+struct AffixClass {
+    prefix: bool,
+    cross_product: bool,
+    rules: Vec<Affix>,
+}
+
+/// The rules of a .aff file
+#[derive(Debug)]
+pub struct Affixes {
+    flag_type: FlagType,
+    latin1: bool,
+    aliases: Vec<Vec<String>>,
+    classes: HashMap<String, AffixClass>,
+    need_affix: Option<String>,
+    forbidden: Option<String>,
+}
+
+impl Affixes {
+    pub fn from_reader<R: Read>(mut reader: R) -> Result<Affixes, String> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).map_err(|e| e.to_string())?;
+        let latin1 = String::from_utf8_lossy(&bytes).lines()
+            .filter_map(|line| line.trim().strip_prefix("SET "))
+            .any(|set| is_latin1(set.trim()));
+
+        let mut affixes = Affixes {
+            flag_type: FlagType::Char,
+            latin1,
+            aliases: Vec::new(),
+            classes: HashMap::new(),
+            need_affix: None,
+            forbidden: None,
+        };
+        for (number, line) in decode(bytes, latin1)?.lines().enumerate() {
+            affixes.parse_line(line).map_err(|e| format!("line {}: {}", number + 1, e))?;
+        }
+        Ok(affixes)
+    }
+
+    fn parse_line(&mut self, line: &str) -> Result<(), String> {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        match fields.as_slice() {
+            ["FLAG", kind, ..] => {
+                self.flag_type = match *kind {
+                    "long" => FlagType::Long,
+                    "num" => FlagType::Num,
+                    "UTF-8" => FlagType::Char,
+                    _ => return Err(format!("unknown flag type {}", kind)),
+                };
+            }
+            // The first AF line holds the number of aliases
+            ["AF", flags, ..] if !(self.aliases.is_empty() && flags.parse::<usize>().is_ok()) => {
+                let flags = self.parse_flags(flags);
+                self.aliases.push(flags);
+            }
+            ["NEEDAFFIX", flag, ..] => self.need_affix = Some(flag.to_string()),
+            ["FORBIDDENWORD", flag, ..] => self.forbidden = Some(flag.to_string()),
+            [kind @ ("PFX" | "SFX"), flag, rest @ ..] => {
+                let prefix = *kind == "PFX";
+                if !self.classes.contains_key(*flag) {
+                    let cross_product = match rest.first() {
+                        Some(&"Y") => true,
+                        Some(&"N") => false,
+                        _ => return Err(format!("bad {} header", kind)),
+                    };
+                    self.classes.insert(flag.to_string(), AffixClass { prefix, cross_product, rules: Vec::new() });
+                    return Ok(());
+                }
+                if rest.len() < 2 {
+                    return Err(format!("bad {} rule", kind));
+                }
+                let (add, continuation) = match rest[1].split_once('/') {
+                    Some((add, flags)) => (add, self.parse_flags(flags)),
+                    None => (rest[1], Vec::new()),
+                };
+                let rule = Affix {
+                    strip: zero_is_empty(rest[0]),
+                    add: zero_is_empty(add),
+                    continuation,
+                    condition: parse_condition(rest.get(2).cloned().unwrap_or("."))?,
+                };
+                let class = self.classes.get_mut(*flag).expect("header inserted above");
+                if class.prefix != prefix {
+                    return Err(format!("flag {} is used for both prefixes and suffixes", flag));
+                }
+                class.rules.push(rule);
+            }
+            _ => (),
+        }
+        Ok(())
+    }
+
+    /// The flags in a flag field, resolving an alias number
+    fn parse_flags(&self, field: &str) -> Vec<String> {
+        if !self.aliases.is_empty() {
+            if let Some(flags) = field.parse::<usize>().ok().and_then(|n| self.aliases.get(n.wrapping_sub(1))) {
+                return flags.clone();
+            }
+        }
+        match self.flag_type {
+            FlagType::Char => field.chars().map(|c| c.to_string()).collect(),
+            FlagType::Long => {
+                let chars: Vec<char> = field.chars().collect();
+                chars.chunks(2).map(|pair| pair.iter().collect()).collect()
+            }
+            FlagType::Num => field.split(',').map(|n| n.trim().to_owned()).filter(|n| !n.is_empty()).collect(),
+        }
+    }
+
+    fn has(&self, flags: &[String], flag: &Option<String>) -> bool {
+        flag.as_ref().is_some_and(|flag| flags.contains(flag))
+    }
+
+    /// Every word a .dic entry stands for: the stem (unless it needs an affix), the stem with
+    /// each of its prefixes and suffixes, and with both when they combine
+    pub fn expand(&self, stem: &str, flags: &str) -> Vec<String> {
+        let flags = self.parse_flags(flags);
+        if self.has(&flags, &self.forbidden) {
+            return Vec::new();
+        }
+        let mut words = Vec::new();
+        if !self.has(&flags, &self.need_affix) {
+            words.push(stem.to_owned());
+        }
+
+        // Suffixed words that may still take a cross product prefix
+        let mut crossable = Vec::new();
+        for class in flags.iter().filter_map(|f| self.classes.get(f)).filter(|c| !c.prefix) {
+            for (word, rule) in class.apply(stem) {
+                self.push_with_continuation(&mut words, &word, rule);
+                if class.cross_product {
+                    crossable.push(word);
+                }
+            }
+        }
+        for class in flags.iter().filter_map(|f| self.classes.get(f)).filter(|c| c.prefix) {
+            for (word, rule) in class.apply(stem) {
+                self.push_with_continuation(&mut words, &word, rule);
+            }
+            if class.cross_product {
+                for suffixed in &crossable {
+                    words.extend(class.apply(suffixed).into_iter().map(|(word, _)| word));
+                }
+            }
+        }
+        words
+    }
+
+    /// An affixed word, and that word with the rule's continuation affixes
+    fn push_with_continuation(&self, words: &mut Vec<String>, word: &str, rule: &Affix) {
+        if !self.has(&rule.continuation, &self.need_affix) {
+            words.push(word.to_owned());
+        }
+        for class in rule.continuation.iter().filter_map(|f| self.classes.get(f)) {
+            words.extend(class.apply(word).into_iter().map(|(word, _)| word));
+        }
+    }
+}
+
+impl AffixClass {
+    /// The word with each rule whose strip and condition it satisfies
+    fn apply(&self, word: &str) -> Vec<(String, &Affix)> {
+        let chars: Vec<char> = word.chars().collect();
+        let mut found = Vec::new();
+        for rule in &self.rules {
+            let n = rule.condition.len();
+            if n > chars.len() {
+                continue;
+            }
+            let (matched, affixed) = if self.prefix {
+                let matched = rule.condition.iter().zip(&chars[..n]).all(|(class, &c)| class.matches(c));
+                (matched, word.strip_prefix(rule.strip.as_str()).map(|rest| format!("{}{}", rule.add, rest)))
+            } else {
+                let matched = rule.condition.iter().zip(&chars[chars.len() - n..]).all(|(class, &c)| class.matches(c));
+                (matched, word.strip_suffix(rule.strip.as_str()).map(|rest| format!("{}{}", rest, rule.add)))
+            };
+            match affixed {
+                Some(affixed) if matched && !affixed.is_empty() => found.push((affixed, rule)),
+                _ => (),
+            }
+        }
+        found
+    }
+}
+
+/// A rule condition: a sequence of characters, `.` and `[...]` / `[^...]` classes
+fn parse_condition(condition: &str) -> Result<Vec<CharClass>, String> {
+    let mut classes = Vec::new();
+    let mut chars = condition.chars();
+    while let Some(c) = chars.next() {
+        classes.push(match c {
+            '.' => CharClass::Any,
+            '[' => {
+                let mut set = Vec::new();
+                let mut negated = false;
+                loop {
+                    match chars.next() {
+                        Some(']') => break,
+                        Some('^') if set.is_empty() && !negated => negated = true,
+                        Some(c) => set.push(c),
+                        None => return Err(format!("unclosed [ in condition {}", condition)),
+                    }
+                }
+                CharClass::Set(negated, set)
+            }
+            c => CharClass::Set(false, vec![c]),
+        });
+    }
+    Ok(classes)
+}
+
+fn zero_is_empty(field: &str) -> String {
+    if field == "0" { String::new() } else { field.to_owned() }
+}
+
+fn is_latin1(encoding: &str) -> bool {
+    matches!(encoding.to_uppercase().as_str(), "ISO8859-1" | "ISO-8859-1" | "LATIN1")
+}
+
+fn decode(bytes: Vec<u8>, latin1: bool) -> Result<String, String> {
+    if latin1 {
+        Ok(bytes.into_iter().map(|b| b as char).collect())
+    } else {
+        String::from_utf8(bytes).map_err(|_| "not valid UTF-8 (and no SET ISO8859-1)".to_owned())
+    }
+}
+
+/// Every word of a .dic file, expanded with `affixes`, normalized, sorted and deduplicated
+pub fn read_dictionary<R: Read>(mut reader: R, affixes: &Affixes) -> Result<Vec<String>, String> {
+    let mut bytes = Vec::new();
+    reader.read_to_end(&mut bytes).map_err(|e| e.to_string())?;
+    let text = decode(bytes, affixes.latin1)?;
+
+    let mut words = Vec::new();
+    let mut lines = text.lines().map(|line| line.trim()).filter(|line| !line.is_empty());
+    // The first line is the (approximate) number of entries
+    if lines.next().is_some_and(|first| first.parse::<usize>().is_err()) {
+        return Err("the first line of a .dic file should be its number of entries".to_owned());
+    }
+    for line in lines {
+        // Morphological fields follow the entry after whitespace
+        let entry = line.split(['\t', ' ']).next().unwrap_or("");
+        let (stem, flags) = split_entry(entry);
+        words.extend(affixes.expand(&stem, flags).into_iter().map(|w| normalize(&w)));
+    }
+    words.sort();
+    words.dedup();
+    Ok(words)
+}
+
+/// Splits `stem/flags` at the first slash that isn't escaped as `\/`
+fn split_entry(entry: &str) -> (String, &str) {
+    let mut escaped = false;
+    for (i, c) in entry.char_indices() {
+        match c {
+            '/' if !escaped => return (entry[..i].replace("\\/", "/"), &entry[i + 1..]),
+            '\\' => escaped = !escaped,
+            _ => escaped = false,
+        }
+    }
+    (entry.replace("\\/", "/"), "")
+}
+
+/// Expands the dictionary at `dic` with the affix file next to it (same name, .aff extension)
+pub fn open<P: AsRef<Path>>(dic: P) -> Result<Vec<String>, String> {
+    let aff = dic.as_ref().with_extension("aff");
+    let affixes = File::open(&aff).map_err(|e| e.to_string())
+        .and_then(Affixes::from_reader)
+        .map_err(|e| format!("{}: {}", aff.display(), e))?;
+    File::open(dic.as_ref()).map_err(|e| e.to_string())
+        .and_then(|f| read_dictionary(f, &affixes))
+        .map_err(|e| format!("{}: {}", dic.as_ref().display(), e))
+}
+
+/// Adds the words the table doesn't have yet, with DICTIONARY_COUNT.
+/// @return usize - the number of words added
+pub fn merge<I: IntoIterator<Item = String>>(table: &mut CountTable, words: I) -> usize {
+    let before = table.len();
+    for word in words {
+        table.entry(word).or_insert(DICTIONARY_COUNT);
+    }
+    table.len() - before
+}
+
+#[cfg(test)]
+mod hunspell_tests {
+    use super::{merge, read_dictionary, Affixes, DICTIONARY_COUNT};
+    use table::form_table;
+
+    static AFF: &str = "SET UTF-8
+TRY esianrtolcdugmphbyfvkwz
+
+# re- prefix
+PFX A Y 1
+PFX A   0     re         .
+
+SFX B Y 2
+SFX B   0     ed         [^y]
+SFX B   y     ied        y
+
+SFX S N 3
+SFX S   y     ies        [^aeiou]y
+SFX S   0     s          [aeiou]y
+SFX S   0     s          [^y]
+";
+
+    fn expand(aff: &str, dic: &str) -> Vec<String> {
+        let affixes = Affixes::from_reader(aff.as_bytes()).unwrap();
+        read_dictionary(dic.as_bytes(), &affixes).unwrap()
+    }
+
+    #[test]
+    fn prefixes_suffixes_and_cross_products() {
+        assert_eq!(expand(AFF, "3\nhello\ntry/B\nwork/AB\n"),
+                   vec!["hello", "rework", "reworked", "tried", "try", "work", "worked"]);
+    }
+
+    #[test]
+    fn conditions() {
+        assert_eq!(expand(AFF, "3\ncity/S\nday/S\nbook/S\n"),
+                   vec!["book", "books", "cities", "city", "day", "days"]);
+    }
+
+    #[test]
+    fn no_cross_product() {
+        let aff = "PFX U N 1\nPFX U 0 un .\nSFX D Y 1\nSFX D 0 ed .\n";
+        assert_eq!(expand(aff, "1\nlock/UD\n"), vec!["lock", "locked", "unlock"]);
+    }
+
+    #[test]
+    fn long_flags_aliases_and_continuation() {
+        let aff = "FLAG long
+AF 2
+AF Aa
+AF BbZz
+NEEDAFFIX Zz
+FORBIDDENWORD Xx
+SFX Aa Y 1
+SFX Aa 0 ful/Bb .
+SFX Bb Y 1
+SFX Bb 0 ness .
+";
+        assert_eq!(expand(aff, "3\nhope/1\nkind/2\nteh/Xx\n"),
+                   vec!["hope", "hopeful", "hopefulness", "kindness"]);
+    }
+
+    #[test]
+    fn entries_with_morphology_case_and_escapes() {
+        let aff = "FLAG num\nSFX 101 Y 1\nSFX 101 0 s .\n";
+        assert_eq!(expand(aff, "3\nParis\tpo:noun\nword/101 st:word\nand\\/or\n"),
+                   vec!["and/or", "paris", "word", "words"]);
+    }
+
+    #[test]
+    fn latin1_encoding() {
+        let aff = b"SET ISO8859-1\nSFX S Y 1\nSFX S 0 s .\n";
+        let affixes = Affixes::from_reader(&aff[..]).unwrap();
+        assert_eq!(read_dictionary(&b"1\ncaf\xe9/S\n"[..], &affixes).unwrap(), vec!["café", "cafés"]);
+    }
+
+    #[test]
+    fn apostrophes_are_removed() {
+        let words = expand(AFF, "2\nDon't\no'clock\n");
+        assert_eq!(words, vec!["dont", "oclock"]);
+    }
+
+    #[test]
+    fn bad_files() {
+        assert!(Affixes::from_reader("FLAG hex\n".as_bytes()).unwrap_err().contains("line 1"));
+        assert!(Affixes::from_reader("SFX A Y 1\nSFX A 0 s [ab\n".as_bytes()).is_err());
+        let affixes = Affixes::from_reader("".as_bytes()).unwrap();
+        assert!(read_dictionary("hello\n".as_bytes(), &affixes).is_err());
+        assert!(read_dictionary(&b"1\n\xff\n"[..], &affixes).is_err());
+    }
+
+    #[test]
+    fn merge_keeps_corpus_counts() {
+        let mut table = form_table("work work the work".as_bytes());
+        let added = merge(&mut table, expand(AFF, "1\nwork/AB\n"));
+        assert_eq!(added, 3);
+        assert_eq!(table["work"], 3);
+        assert_eq!(table["reworked"], DICTIONARY_COUNT);
+        assert_eq!(table.len(), 5);
+    }
+}
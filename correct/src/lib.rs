@@ -1,3 +1,26 @@
+extern crate regex;
+
+pub mod table;
+pub mod edits;
+pub mod channel;
+pub mod corrector;
+pub mod context;
+pub mod text;
+pub mod model;
+pub mod json;
+pub mod suggest;
+pub mod personal;
+pub mod serve;
+pub mod source;
+pub mod lsp;
+pub mod eval;
+pub mod compound;
+pub mod phonetic;
+pub mod bktree;
+pub mod batch;
+pub mod hunspell;
+pub mod complete;
+
 #[test]
 fn it_works() {
 }
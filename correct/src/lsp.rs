@@ -0,0 +1,500 @@
+#![doc="
+    Language Server Protocol front end, so any LSP-capable editor gets spell checking. Messages
+    are JSON-RPC framed with a Content-Length header on stdin/stdout.
+
+    The server keeps every open document (full sync), publishes a diagnostic per misspelled word
+    whenever a document is opened or changed, and answers code action requests with one quick
+    fix per top suggestion. Which parts of a document are checked depends on its language id,
+    see the source module.
+
+    Assumptions:
+        - Positions are UTF-16 code units within a line, as LSP specifies by default
+        - Documents are small enough to re-check whole on every change
+        - A message that can't be read is answered with a parse error and skipped, bodies over
+          MAX_MESSAGE and header lines over MAX_HEADER_LINE without being read
+"]
+
+use std::collections::HashMap;
+use std::io::{self, BufRead, Read, Write};
+
+use corrector::Corrector;
+use json::Json;
+use source::{self, Syntax};
+use text::Misspelling;
+
+/// Quick fixes offered per misspelled word
+pub const CODE_ACTIONS: usize = 3;
+/// LSP DiagnosticSeverity.Information
+const SEVERITY: f64 = 3.0;
+/// LSP TextDocumentSyncKind.Full
+const FULL_SYNC: f64 = 1.0;
+/// JSON-RPC error code for a message that can't be parsed
+const PARSE_ERROR: f64 = -32700.0;
+/// Larger message bodies are skipped unread and answered with a parse error
+pub const MAX_MESSAGE: usize = 16 << 20;
+/// Longer header lines are skipped unread and answered with a parse error
+pub const MAX_HEADER_LINE: usize = 8 << 10;
+
+/// Reads one framed message.
+/// @return Ok(None) - at the end of input
+/// @return Ok(Some(Err(reason))) - for a message that can't be parsed; it is skipped, so the
+/// next call reads the message after it
+pub fn read_message<R: BufRead>(reader: &mut R) -> io::Result<Option<Result<Json, String>>> {
+    let mut length = None;
+    let mut bad_header = None;
+    let mut line = String::new();
+
+    loop {
+        line.clear();
+        if (&mut *reader).take(MAX_HEADER_LINE as u64 + 1).read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+        if line.len() > MAX_HEADER_LINE {
+            if !line.ends_with('\n') {
+                skip_line(reader)?;
+            }
+            bad_header = Some(format!("header line over the limit of {} bytes", MAX_HEADER_LINE));
+            continue;
+        }
+        let header = line.trim_end();
+        if header.is_empty() {
+            if length.is_some() || bad_header.is_some() {
+                break;
+            }
+            continue;
+        }
+        if let Some(colon) = header.find(':') {
+            if header[..colon].eq_ignore_ascii_case("content-length") {
+                match header[colon + 1..].trim().parse::<usize>() {
+                    Ok(parsed) => length = Some(parsed),
+                    Err(_) => bad_header = Some(format!("bad header {}", header)),
+                }
+            }
+        }
+    }
+
+    // Without a length there is no telling where the body ends; whatever follows is read as
+    // the next message's headers until a blank line
+    let length = match (bad_header, length) {
+        (Some(bad_header), _) => return Ok(Some(Err(bad_header))),
+        (None, length) => length.unwrap_or(0),
+    };
+    if length > MAX_MESSAGE {
+        io::copy(&mut reader.take(length as u64), &mut io::sink())?;
+        return Ok(Some(Err(format!("message of {} bytes is over the limit of {}", length, MAX_MESSAGE))));
+    }
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body)?;
+    Ok(Some(String::from_utf8(body).map_err(|_| "message is not UTF-8".to_owned())
+        .and_then(|body| Json::parse(&body))))
+}
+
+pub fn write_message<W: Write>(writer: &mut W, message: &Json) -> io::Result<()> {
+    let body = message.to_string();
+    write!(writer, "Content-Length: {}\r\n\r\n{}", body.len(), body)?;
+    writer.flush()
+}
+
+/// Line starts of a text, to convert between byte offsets and LSP positions
+pub struct LineIndex<'a> {
+    text: &'a str,
+    starts: Vec<usize>,
+}
+
+impl<'a> LineIndex<'a> {
+    pub fn new(text: &'a str) -> LineIndex<'a> {
+        let mut starts = vec![0];
+        starts.extend(text.match_indices('\n').map(|(i, _)| i + 1));
+        LineIndex { text, starts }
+    }
+
+    /// (line, UTF-16 character) of a byte offset
+    pub fn position(&self, offset: usize) -> (usize, usize) {
+        let line = match self.starts.binary_search(&offset) {
+            Ok(line) => line,
+            Err(next) => next - 1,
+        };
+        let character = self.text[self.starts[line]..offset].encode_utf16().count();
+        (line, character)
+    }
+
+    /// Byte offset of a (line, UTF-16 character) position, clamped to the text
+    pub fn offset(&self, line: usize, character: usize) -> usize {
+        let start = match self.starts.get(line) {
+            Some(&start) => start,
+            None => return self.text.len(),
+        };
+        let mut units = 0;
+        for (i, c) in self.text[start..].char_indices() {
+            if units >= character || c == '\n' {
+                return start + i;
+            }
+            units += c.len_utf16();
+        }
+        self.text.len()
+    }
+
+    pub fn range(&self, start: usize, end: usize) -> Json {
+        Json::object(vec![("start", self.position_json(start)), ("end", self.position_json(end))])
+    }
+
+    fn position_json(&self, offset: usize) -> Json {
+        let (line, character) = self.position(offset);
+        Json::object(vec![("line", Json::Number(line as f64)), ("character", Json::Number(character as f64))])
+    }
+}
+
+struct Document {
+    syntax: Syntax,
+    text: String,
+    misspellings: Vec<Misspelling>,
+}
+
+pub struct Server<'a> {
+    corrector: &'a Corrector,
+    documents: HashMap<String, Document>,
+    shutdown: bool,
+}
+
+impl<'a> Server<'a> {
+    pub fn new(corrector: &'a Corrector) -> Server<'a> {
+        Server { corrector, documents: HashMap::new(), shutdown: false }
+    }
+
+    /// Serves until `exit` or the end of input.
+    /// @return i32 - the exit code: 0 if the client asked for shutdown first, as LSP specifies
+    pub fn run<R: BufRead, W: Write>(&mut self, mut reader: R, mut writer: W) -> io::Result<i32> {
+        while let Some(message) = read_message(&mut reader)? {
+            let message = match message {
+                Ok(message) => message,
+                Err(reason) => {
+                    write_message(&mut writer, &parse_error(&reason))?;
+                    continue;
+                }
+            };
+            if message.get("method").and_then(|m| m.as_str()) == Some("exit") {
+                return Ok(if self.shutdown { 0 } else { 1 });
+            }
+            for reply in self.handle(&message) {
+                write_message(&mut writer, &reply)?;
+            }
+        }
+        Ok(1)
+    }
+
+    /// Responses and notifications to send for one incoming message
+    pub fn handle(&mut self, message: &Json) -> Vec<Json> {
+        let method = message.get("method").and_then(|m| m.as_str()).unwrap_or("");
+        let params = message.get("params").unwrap_or(&Json::Null);
+        let id = match message.get("id") {
+            Some(id) => id.clone(),
+            None => return self.notification(method, params),
+        };
+
+        let outcome = match method {
+            "initialize" => ("result", capabilities()),
+            "shutdown" => {
+                self.shutdown = true;
+                ("result", Json::Null)
+            }
+            "textDocument/codeAction" => ("result", self.code_actions(params)),
+            _ => ("error", Json::object(vec![
+                ("code", Json::Number(-32601.0)),
+                ("message", Json::String(format!("method not found: {}", method))),
+            ])),
+        };
+        vec![Json::object(vec![("jsonrpc", Json::string("2.0")), ("id", id), outcome])]
+    }
+
+    fn notification(&mut self, method: &str, params: &Json) -> Vec<Json> {
+        let document = params.get("textDocument").unwrap_or(&Json::Null);
+        let uri = match document.get("uri").and_then(|u| u.as_str()) {
+            Some(uri) => uri.to_owned(),
+            None => return Vec::new(),
+        };
+
+        match method {
+            "textDocument/didOpen" => {
+                let language = document.get("languageId").and_then(|l| l.as_str()).unwrap_or("");
+                let text = document.get("text").and_then(|t| t.as_str()).unwrap_or("");
+                self.check(&uri, Syntax::from_language_id(language), text.to_owned());
+            }
+            "textDocument/didChange" => {
+                // Full sync: the last change holds the whole text
+                let text = params.get("contentChanges").and_then(|c| c.as_array())
+                    .and_then(|changes| changes.last())
+                    .and_then(|change| change.get("text")).and_then(|t| t.as_str());
+                let syntax = self.documents.get(&uri).map_or(Syntax::Plain, |d| d.syntax);
+                match text {
+                    Some(text) => self.check(&uri, syntax, text.to_owned()),
+                    None => return Vec::new(),
+                }
+            }
+            "textDocument/didClose" => {
+                self.documents.remove(&uri);
+                return vec![publish(&uri, Vec::new())];
+            }
+            _ => return Vec::new(),
+        }
+        vec![self.diagnostics(&uri)]
+    }
+
+    fn check(&mut self, uri: &str, syntax: Syntax, text: String) {
+        let misspellings = source::misspellings(&text, syntax, self.corrector);
+        self.documents.insert(uri.to_owned(), Document { syntax, text, misspellings });
+    }
+
+    fn diagnostics(&self, uri: &str) -> Json {
+        let document = &self.documents[uri];
+        let index = LineIndex::new(&document.text);
+        let diagnostics = document.misspellings.iter().map(|m| diagnostic(m, &index)).collect();
+        publish(uri, diagnostics)
+    }
+
+    /// A quick fix for each of the top suggestions of every misspelling in the requested range
+    fn code_actions(&self, params: &Json) -> Json {
+        let uri = params.get("textDocument").and_then(|d| d.get("uri")).and_then(|u| u.as_str()).unwrap_or("");
+        let document = match self.documents.get(uri) {
+            Some(document) => document,
+            None => return Json::Array(Vec::new()),
+        };
+        let index = LineIndex::new(&document.text);
+        let (start, end) = match params.get("range") {
+            Some(range) => (offset_of(&index, range.get("start")), offset_of(&index, range.get("end"))),
+            None => (0, document.text.len()),
+        };
+
+        let mut actions = Vec::new();
+        for misspelling in &document.misspellings {
+            if misspelling.offset > end || misspelling.end() < start {
+                continue;
+            }
+            for suggestion in misspelling.suggestions.iter().take(CODE_ACTIONS) {
+                let replacement = misspelling.replacement(suggestion);
+                let edit = Json::object(vec![
+                    ("range", index.range(misspelling.offset, misspelling.end())),
+                    ("newText", Json::string(&replacement)),
+                ]);
+                actions.push(Json::object(vec![
+                    ("title", Json::String(format!("Change to '{}'", replacement))),
+                    ("kind", Json::string("quickfix")),
+                    ("diagnostics", Json::Array(vec![diagnostic(misspelling, &index)])),
+                    ("edit", Json::object(vec![
+                        ("changes", Json::Object(vec![(uri.to_owned(), Json::Array(vec![edit]))])),
+                    ])),
+                ]));
+            }
+        }
+        Json::Array(actions)
+    }
+}
+
+/// Skips the rest of the current line without holding on to it
+fn skip_line<R: BufRead>(reader: &mut R) -> io::Result<()> {
+    loop {
+        let (done, used) = {
+            let buffer = reader.fill_buf()?;
+            match buffer.iter().position(|&b| b == b'\n') {
+                Some(i) => (true, i + 1),
+                None => (buffer.is_empty(), buffer.len()),
+            }
+        };
+        reader.consume(used);
+        if done {
+            return Ok(());
+        }
+    }
+}
+
+/// Response to a message that couldn't be parsed, which has no id to answer
+fn parse_error(reason: &str) -> Json {
+    Json::object(vec![
+        ("jsonrpc", Json::string("2.0")),
+        ("id", Json::Null),
+        ("error", Json::object(vec![
+            ("code", Json::Number(PARSE_ERROR)),
+            ("message", Json::String(format!("parse error: {}", reason))),
+        ])),
+    ])
+}
+
+fn capabilities() -> Json {
+    Json::object(vec![
+        ("capabilities", Json::object(vec![
+            ("textDocumentSync", Json::Number(FULL_SYNC)),
+            ("codeActionProvider", Json::Bool(true)),
+        ])),
+        ("serverInfo", Json::object(vec![("name", Json::string("correct"))])),
+    ])
+}
+
+fn offset_of(index: &LineIndex, position: Option<&Json>) -> usize {
+    let field = |name| position.and_then(|p| p.get(name)).and_then(|n| n.as_f64()).unwrap_or(0.0) as usize;
+    index.offset(field("line"), field("character"))
+}
+
+fn diagnostic(misspelling: &Misspelling, index: &LineIndex) -> Json {
+    let message = match misspelling.suggestions.first() {
+        Some(best) => format!("Unknown word '{}', did you mean '{}'?", misspelling.word, misspelling.replacement(best)),
+        None => format!("Unknown word '{}'", misspelling.word),
+    };
+    Json::object(vec![
+        ("range", index.range(misspelling.offset, misspelling.end())),
+        ("severity", Json::Number(SEVERITY)),
+        ("source", Json::string("correct")),
+        ("message", Json::String(message)),
+    ])
+}
+
+fn publish(uri: &str, diagnostics: Vec<Json>) -> Json {
+    Json::object(vec![
+        ("jsonrpc", Json::string("2.0")),
+        ("method", Json::string("textDocument/publishDiagnostics")),
+        ("params", Json::object(vec![("uri", Json::string(uri)), ("diagnostics", Json::Array(diagnostics))])),
+    ])
+}
+
+#[cfg(test)]
+mod lsp_tests {
+    use super::{read_message, write_message, LineIndex, Server, MAX_HEADER_LINE, MAX_MESSAGE};
+    use corrector::Corrector;
+    use json::Json;
+    use table::form_table;
+
+    fn corrector() -> Corrector {
+        Corrector::new(form_table("the quick brown fox jumps over the lazy dog say hello".as_bytes()))
+    }
+
+    /// Frames the messages as a client would write them to the server's stdin
+    fn script(messages: &[&str]) -> Vec<u8> {
+        let mut input = Vec::new();
+        for message in messages {
+            write_message(&mut input, &Json::parse(message).unwrap()).unwrap();
+        }
+        input
+    }
+
+    fn replies(output: &[u8]) -> Vec<Json> {
+        let mut reader = output;
+        let mut messages = Vec::new();
+        while let Some(message) = read_message(&mut reader).unwrap() {
+            messages.push(message.unwrap());
+        }
+        messages
+    }
+
+    fn field<'a>(json: &'a Json, path: &[&str]) -> &'a Json {
+        path.iter().fold(json, |json, key| json.get(key).unwrap_or_else(|| panic!("no {} in {}", key, json)))
+    }
+
+    #[test]
+    fn framing_round_trip() {
+        let mut out = Vec::new();
+        write_message(&mut out, &Json::object(vec![("id", Json::Number(1.0))])).unwrap();
+        assert_eq!(out, b"Content-Length: 8\r\n\r\n{\"id\":1}");
+        let mut reader = &out[..];
+        assert_eq!(read_message(&mut reader).unwrap(), Some(Ok(Json::object(vec![("id", Json::Number(1.0))]))));
+        assert_eq!(read_message(&mut reader).unwrap(), None);
+    }
+
+    #[test]
+    fn bad_messages_are_skipped() {
+        let mut input = b"Content-Length: 3\r\n\r\n{x}".to_vec();
+        input.extend_from_slice(b"Content-Length: nine\r\n\r\n");
+        input.extend_from_slice(format!("Content-Length: {}\r\n\r\n", MAX_MESSAGE + 1).as_bytes());
+        input.extend(vec![b'['; MAX_MESSAGE + 1]);
+        input.extend(format!("X-Long: {}\r\n\r\n", "a".repeat(MAX_HEADER_LINE * 4)).into_bytes());
+        input.extend(script(&[r#"{"jsonrpc":"2.0","id":1,"method":"shutdown"}"#, r#"{"jsonrpc":"2.0","method":"exit"}"#]));
+
+        let mut output = Vec::new();
+        assert_eq!(Server::new(&corrector()).run(&input[..], &mut output).unwrap(), 0);
+        let replies = replies(&output);
+        assert_eq!(replies.len(), 5);
+        for reply in &replies[..4] {
+            assert_eq!(field(reply, &["id"]), &Json::Null);
+            assert_eq!(field(reply, &["error", "code"]), &Json::Number(-32700.0));
+        }
+        assert_eq!(field(&replies[4], &["id"]), &Json::Number(1.0));
+    }
+
+    #[test]
+    fn line_index() {
+        let text = "héllo\n😀 wrold\n";
+        let index = LineIndex::new(text);
+        let world = text.find("wrold").unwrap();
+        assert_eq!(index.position(world), (1, 3));
+        assert_eq!(index.offset(1, 3), world);
+        assert_eq!(index.position(0), (0, 0));
+        assert_eq!(index.offset(0, 99), 6);
+        assert_eq!(index.offset(9, 0), text.len());
+    }
+
+    #[test]
+    fn scripted_session() {
+        let input = script(&[
+            r#"{"jsonrpc":"2.0","id":1,"method":"initialize","params":{"capabilities":{}}}"#,
+            r#"{"jsonrpc":"2.0","method":"initialized","params":{}}"#,
+            r#"{"jsonrpc":"2.0","method":"textDocument/didOpen","params":{"textDocument":
+                {"uri":"file:///a.rs","languageId":"rust","version":1,
+                 "text":"fn quikc() {}\n// Teh lazy dog\nlet s = \"say helo\";\n"}}}"#,
+            r#"{"jsonrpc":"2.0","id":2,"method":"textDocument/codeAction","params":{
+                "textDocument":{"uri":"file:///a.rs"},
+                "range":{"start":{"line":1,"character":4},"end":{"line":1,"character":4}},
+                "context":{"diagnostics":[]}}}"#,
+            r#"{"jsonrpc":"2.0","method":"textDocument/didChange","params":{
+                "textDocument":{"uri":"file:///a.rs","version":2},
+                "contentChanges":[{"text":"// the lazy dog\n"}]}}"#,
+            r#"{"jsonrpc":"2.0","method":"textDocument/didClose","params":{"textDocument":{"uri":"file:///a.rs"}}}"#,
+            r#"{"jsonrpc":"2.0","id":3,"method":"shutdown"}"#,
+            r#"{"jsonrpc":"2.0","method":"exit"}"#,
+        ]);
+        let mut output = Vec::new();
+        let code = Server::new(&corrector()).run(&input[..], &mut output).unwrap();
+        assert_eq!(code, 0);
+
+        let replies = replies(&output);
+        assert_eq!(replies.len(), 6);
+        assert_eq!(field(&replies[0], &["id"]), &Json::Number(1.0));
+        assert_eq!(field(&replies[0], &["result", "capabilities", "codeActionProvider"]), &Json::Bool(true));
+
+        // The identifier quikc is code, only the comment and the string are checked
+        let diagnostics = field(&replies[1], &["params", "diagnostics"]).as_array().unwrap();
+        assert_eq!(diagnostics.len(), 2);
+        assert_eq!(field(&diagnostics[0], &["range", "start"]).to_string(), r#"{"line":1,"character":3}"#);
+        assert_eq!(field(&diagnostics[0], &["range", "end"]).to_string(), r#"{"line":1,"character":6}"#);
+        assert_eq!(field(&diagnostics[0], &["message"]), &Json::string("Unknown word 'Teh', did you mean 'The'?"));
+        assert_eq!(field(&diagnostics[1], &["range", "start"]).to_string(), r#"{"line":2,"character":13}"#);
+
+        let actions = field(&replies[2], &["result"]).as_array().unwrap();
+        assert!(!actions.is_empty());
+        assert_eq!(field(&actions[0], &["title"]), &Json::string("Change to 'The'"));
+        let edits = field(&actions[0], &["edit", "changes", "file:///a.rs"]).as_array().unwrap();
+        assert_eq!(field(&edits[0], &["newText"]), &Json::string("The"));
+
+        assert_eq!(field(&replies[3], &["params", "diagnostics"]), &Json::Array(vec![]));
+        assert_eq!(field(&replies[4], &["params", "diagnostics"]), &Json::Array(vec![]));
+        assert_eq!(field(&replies[5], &["id"]), &Json::Number(3.0));
+        assert_eq!(field(&replies[5], &["result"]), &Json::Null);
+    }
+
+    #[test]
+    fn markdown_and_unknown_methods() {
+        let corrector = corrector();
+        let mut server = Server::new(&corrector);
+        let open = Json::parse(r#"{"method":"textDocument/didOpen","params":{"textDocument":
+            {"uri":"u","languageId":"markdown","text":"The qiuck fox `qiuck`\n```\nqiuck\n```\n"}}}"#).unwrap();
+        let published = server.handle(&open);
+        assert_eq!(field(&published[0], &["params", "diagnostics"]).as_array().unwrap().len(), 1);
+
+        let unknown = Json::parse(r#"{"id":"x","method":"workspace/symbol","params":{}}"#).unwrap();
+        let reply = server.handle(&unknown);
+        assert_eq!(field(&reply[0], &["id"]), &Json::string("x"));
+        assert_eq!(field(&reply[0], &["error", "code"]), &Json::Number(-32601.0));
+    }
+
+    #[test]
+    fn exit_without_shutdown() {
+        let input = script(&[r#"{"jsonrpc":"2.0","method":"exit"}"#]);
+        assert_eq!(Server::new(&corrector()).run(&input[..], Vec::new()).unwrap(), 1);
+    }
+}
@@ -1,425 +1,547 @@
-use std::io::{BufRead,BufReader,Read,stdin};
+extern crate correct;
+
+use std::io::{BufRead,BufReader,BufWriter,Read,stdin,stdout};
 use std::env;
-use std::fs::File;
+use std::fs::{self, File};
+use std::path::Path;
+use std::process;
+
+use correct::batch::{self, line_result};
+use correct::channel::read_pairs;
+use correct::complete::Trie;
+use correct::context::ContextModel;
+use correct::corrector::{Corrector, EDITS_MAX_DISTANCE};
+use correct::eval::evaluate;
+use correct::hunspell;
+use correct::model::{Model, WeightedSum};
+use correct::personal::PersonalDictionary;
+use correct::phonetic::{Algorithm, PhoneticIndex, DEFAULT_PHONETIC_WEIGHT};
+use correct::serve::{self, DEFAULT_ADDRESS};
+use correct::lsp::LineIndex;
+use correct::source::{code_misspellings, misspellings, Syntax};
+use correct::suggest::{self, DEFAULT_SUGGESTIONS};
+use correct::text::{correct_text, correct_text_in_context};
 
 #[doc="
     Takes in a training file as argument, and counts the frequencies of the words in the training
     file.
-    Then takes in inputs from stdin, and finds the 'nearest' correction to each word and prints it. 
-    If the word already seems to be a correct word, it won't correct it. 
+    Then takes in inputs from stdin, and finds the 'nearest' correction to each word and prints it.
+    If the word already seems to be a correct word, it won't correct it.
     If the word doesn't have any corrections available, '-' will be printed.
+    Words are looked up lowercased, the way training counts them, and a correction is printed
+    with the capitalization of the typed word (Helo -> Hello, HELO -> HELLO).
+
+    Known words one edit away always win over known words two edits away. Optionally, a file of
+    misspelling/correction pairs can be given with --errors to weight individual edits.
+
+    With --text, stdin is read as running prose instead, and the corrected text is printed with
+    its punctuation, whitespace and capitalization intact. Adding --annotate prints the list of
+    changes (byte offset: original -> replacement) instead of the text. Adding --context also
+    counts bigrams during training and picks each word using its neighbours, so real-word errors
+    (their / there) get fixed too.
+
+    Training on a large corpus is slow, so `correct train corpus.txt -o model.bin` saves the
+    trained model, and model.bin can then be passed wherever a training file is expected.
+    `correct train` takes several sources (text or models), each optionally weighted as
+    path:weight, and --min-count N drops words counted fewer than N times in all (likely
+    typos in the corpus), reporting the vocabulary size before and after.
+
+    --interactive prompts for words and lists the top suggestions (-n, default 5) for each with
+    their edit distance and corpus probability. --json reads one word per line and prints one
+    JSON object per word with every candidate and its scores.
+
+    Words in the personal dictionary (--dict, one word per line) are always accepted, as are
+    URLs, emails, hex strings and CamelCase identifiers, plus anything matching a regex from
+    --ignore. `correct add --dict words.txt word...` or `:add word` in the interactive mode
+    grows the dictionary; without --dict, `:add` only lasts for the session.
+
+    --hunspell en_US.dic adds every word of a Hunspell dictionary, expanded with the prefix and
+    suffix rules of en_US.aff, to the vocabulary. Words the corpus doesn't have are known but
+    rank like a corpus word seen once. `correct train` takes --hunspell too, saving the expansion in
+    the model.
+
+    --phonetic metaphone (or soundex) also offers known words that sound like the typed word
+    (fone -> phone), scored by their probability times --phonetic-weight (default 1).
+    `correct train --phonetic metaphone` stores the phonetic index in the model, which is then
+    used without --phonetic.
+
+    --min-confidence C (0 to 1) abstains from corrections the corrector isn't sure of: the
+    confidence is the best candidate's share of its tier's score, halved for every edit beyond
+    the first. The one word per line mode flags such words for review with their best guess,
+    --text leaves them as they are, --json gives a null correction; every mode but --context.
+
+    --search bktree finds candidates with a BK-tree over the vocabulary instead of generating
+    edits, and with --max-distance N looks farther than two edits away.
+
+    --complete reads one prefix per line and prints the -n (default 5) most frequent known
+    words starting with it, most frequent first. Adding --fuzzy fills the list up with
+    completions of prefixes one typo away.
+
+    With --threads N, the one word per line and --json modes read the whole input first, correct
+    each distinct word once on N threads, and print the results in input order.
+
+    `correct serve model.bin [--addr host:port]` loads the model once and answers
+    `POST /check` requests with the misspelled words of a JSON {\"text\": ...} body, their byte
+    offsets and their ranked suggestions.
+
+    `correct source model.bin src/*.rs` checks the comments, doc comments, string literals and
+    identifier words (split at underscores and case changes) of source files, printing
+    file:line:column diagnostics with the top -n suggestions (default 3). The language comes
+    from the file extension: Rust and other C-like languages, # comment languages, Markdown,
+    and anything else as plain text. --no-identifiers leaves identifiers alone. The exit code
+    is 1 if anything was found.
+
+    `correct eval pairs.txt model.bin` corrects every misspelling of a test set (misspelling ->
+    correction pairs, Norvig's `right: wrong wrong` lines or the Birkbeck format) and reports
+    accuracy, recall within the top -n suggestions, the unknown-word rate, accuracy per edit
+    distance, the average latency and every failure.
 
 Author: James Whang (syw973, sungyoonwhang2017@u.northwestern.edu)
 
 Assumptions:
     Training file doesn't have typos (hopefully)
-    One word per line in the input text to be corrected
+    One word per line in the input text to be corrected, unless --text or --interactive is given
 "]
-
-type CountTable = std::collections::HashMap<String, usize>;
-type Candidates = std::vec::Vec<String>;
-type Edits = std::collections::HashSet<String>;
-static ALPHABETS : &'static str = "abcdefghijklmnopqrstuvwxyz";
-
 fn main() {
-    let args: Vec<_> = env::args().collect();
-    if args.len() != 2 {
-        panic!("Usage: ./correct [train file] ")
-    }
-    let f = File::open(&args[1]).expect("Error opening training file ");
-    let table = form_table(f); 
-
-    correct(stdin(), table);
-}
-
-fn correct<R: Read>(reader: R, table: CountTable) {
-    let mut lines = BufReader::new(reader).lines();
-
-    while let Some(Ok(word)) = lines.next() {
-        if table.contains_key(&word) { // If the word is already known, no need to go through this 
-            println!("{}", word);
-            continue;
+    let args: Vec<String> = env::args().skip(1).collect();
+    match args.first().map(|a| a.as_str()) {
+        Some("train") => return train(args[1..].to_vec()),
+        Some("add") => return add_words(args[1..].to_vec()),
+        _ => (),
+    }
+    let options = match args.first().map(|a| a.as_str()) {
+        Some("serve") => parse_serve_args(args[1..].to_vec()),
+        Some("eval") => parse_eval_args(args[1..].to_vec()),
+        Some("source") => parse_source_args(args[1..].to_vec()),
+        _ => parse_args(args),
+    };
+
+    let Model { mut table, bigrams, phonetic } = Model::open(&options.train)
+        .unwrap_or_else(|e| fail(&format!("Error loading {}: {}", options.train, e)));
+    if let Some(ref path) = options.hunspell {
+        hunspell::merge(&mut table, hunspell::open(path).unwrap_or_else(|e| fail(&format!("Error loading {}", e))));
+    }
+    let mut corrector = Corrector::from_files(table, options.errors.as_deref(), options.dict.as_deref(),
+                                              options.ignore.as_deref())
+        .unwrap_or_else(|e| fail(&format!("Error loading {}", e)));
+    if options.bktree {
+        corrector.set_bktree(options.max_distance.unwrap_or(EDITS_MAX_DISTANCE));
+    }
+    if let Some(min_confidence) = options.min_confidence {
+        corrector.set_min_confidence(min_confidence);
+    }
+    // The index stored by `train --phonetic` is used unless another key is asked for, or
+    // --hunspell added words it doesn't have
+    let weight = options.phonetic_weight.unwrap_or(DEFAULT_PHONETIC_WEIGHT);
+    match (options.phonetic.or_else(|| phonetic.as_ref().map(|index| index.algorithm())), phonetic) {
+        (Some(algorithm), Some(index)) if index.algorithm() == algorithm && options.hunspell.is_none() =>
+            corrector.set_phonetic_index(index, weight),
+        (Some(algorithm), _) => corrector.set_phonetic(algorithm, weight),
+        (None, _) if options.phonetic_weight.is_some() => panic!("{}", USAGE),
+        (None, _) => (),
+    }
+
+    match options.mode {
+        Mode::Lines => match options.threads {
+            Some(threads) => batch::correct_lines(stdin(), stdout(), &corrector, threads).expect("Error writing output "),
+            None => correct(stdin(), &corrector),
+        },
+        Mode::Text => {
+            let context = if options.context { Some(ContextModel::new(bigrams)) } else { None };
+            correct_prose(stdin(), &corrector, context.as_ref(), options.annotate);
         }
-
-        let edit_twos = edits_two(&word);
-
-        let mut closest = "-".to_owned(); // String to hold the closest word.
-        let mut closest_count = 0; // If count gets left as 0, there is no word "close enough" to
-                                    // the given test word
-        
-        for edit_two_word in edit_twos.iter() {
-            if table.contains_key(edit_two_word) {
-                if table[edit_two_word] > closest_count {
-                    closest = edit_two_word.to_owned();
-                    closest_count = table[edit_two_word];
-                }
+        Mode::Interactive => {
+            let input = stdin();
+            suggest::repl(input.lock(), stdout(), &mut corrector, options.suggestions)
+                .expect("Error in interactive session ");
+        }
+        Mode::Json => match options.threads {
+            Some(threads) => batch::json_lines(stdin(), stdout(), &corrector, threads),
+            None => suggest::batch(stdin(), stdout(), &corrector),
+        }.expect("Error writing output "),
+        Mode::Complete => complete(stdin(), &Trie::new(corrector.table()), options.suggestions, options.fuzzy),
+        Mode::Serve => {
+            let address = options.address.as_ref().map_or(DEFAULT_ADDRESS, |a| a.as_str());
+            println!("Checking spelling on http://{}/check", address);
+            serve::serve(address, corrector).unwrap_or_else(|e| fail(&format!("Error serving on {}: {}", address, e)));
+        }
+        Mode::Source => {
+            if !check_sources(&options.files, &corrector, options.suggestions, options.identifiers) {
+                process::exit(1);
             }
         }
-        println!("{}, {}", word, closest);
+        Mode::Eval => {
+            let path = options.pairs.as_ref().expect(USAGE);
+            let pairs = read_pairs(File::open(path).unwrap_or_else(|e| fail(&format!("Error loading {}: {}", path, e))));
+            print!("{}", evaluate(&pairs, &corrector, options.suggestions));
+        }
     }
 }
 
-fn edits_two(word: &String) -> Edits {
-    let mut edits = Edits::new();
-    let edit_ones = edits_one(&word);
-
-    for edit_one_word in edit_ones.iter() {
-        edits = edits.union(&edits_one(&edit_one_word.to_owned())).cloned().collect();
-    }
-    edits
+static USAGE: &str = "Usage: ./correct [train file or model] [--errors pairs file] [--dict words file]
+                 [--ignore patterns file] [--hunspell dic file] [--min-confidence c]
+                 [--phonetic metaphone|soundex [--phonetic-weight w]] [--search edits|bktree [--max-distance n]] [mode]
+    modes: (none) [--threads n]            one word per line
+           --text [--annotate] [--context] running prose
+           --interactive [-n suggestions]  ranked suggestions for typed words
+           --json [--threads n]            one JSON line of candidates per word
+           --complete [-n k] [--fuzzy]     the k most frequent words starting with each line
+       ./correct train [train file or model[:weight]...] [--min-count n] [--hunspell dic file]
+                       [--phonetic metaphone|soundex] -o [model file]
+       ./correct add --dict [words file] [word...]
+       ./correct serve [train file or model] [--addr host:port] [--errors ...] [--dict ...] [--ignore ...]
+       ./correct source [train file or model] [source file...] [-n suggestions] [--no-identifiers] [--dict ...] ...
+       ./correct eval [pairs file] [train file or model] [-n suggestions] [--errors ...] [--dict ...] [--ignore ...]";
+
+fn fail(message: &str) -> ! {
+    eprintln!("{}", message);
+    process::exit(1);
 }
 
-fn edits_one(word: &String) -> Edits {
-    let deletes = find_deletions(word.to_owned());
-    let replaces = find_replacements(word.to_owned());
-    let inserts = find_insertions(word.to_owned());
-    let trans = find_transpositions(word.to_owned());
-
-    let mut edits = Edits::new();
-
-    edits = edits.union(&deletes).cloned().collect();
-    edits = edits.union(&replaces).cloned().collect();
-    edits = edits.union(&inserts).cloned().collect();
-    edits = edits.union(&trans).cloned().collect();
-    edits
+/// `correct train corpus.txt [other.txt:0.5...] [--min-count 2] [--hunspell en_US.dic] [--phonetic metaphone] -o model.bin`
+fn train(mut args: Vec<String>) {
+    let dictionary = take_option(&mut args, "--hunspell");
+    let phonetic = take_option(&mut args, "--phonetic").map(|a| Algorithm::from_name(&a).expect(USAGE));
+    let min_count: Option<usize> = take_option(&mut args, "--min-count").map(|n| n.parse().expect(USAGE));
+    let output = &take_option(&mut args, "-o").expect(USAGE);
+    if args.is_empty() || args.iter().any(|a| a.starts_with('-')) {
+        panic!("{}", USAGE);
+    }
+
+    let mut sum = WeightedSum::default();
+    for source in &args {
+        let (path, weight) = weighted_source(source);
+        let trained = Model::open(path).unwrap_or_else(|e| fail(&format!("Error loading {}: {}", path, e)));
+        println!("{}: {} words, weight {}", path, trained.table.len(), weight);
+        sum.add(trained, weight);
+    }
+    let (mut model, dropped) = sum.finish(min_count.unwrap_or(0));
+    match min_count {
+        Some(min_count) => println!("Vocabulary: {} words, {} after dropping {} counted fewer than {} times",
+                                    model.table.len() + dropped, model.table.len(), dropped, min_count),
+        None if dropped > 0 => println!("Vocabulary: {} words, {} after dropping {} with a weight of 0",
+                                        model.table.len() + dropped, model.table.len(), dropped),
+        None => (),
+    }
+    if let Some(ref path) = dictionary {
+        let words = hunspell::open(path).unwrap_or_else(|e| fail(&format!("Error loading {}", e)));
+        println!("Added {} words from {}", hunspell::merge(&mut model.table, words), path);
+    }
+    if let Some(algorithm) = phonetic {
+        model.phonetic = Some(PhoneticIndex::new(algorithm, &model.table));
+        println!("Indexed the vocabulary by {} key", algorithm.name());
+    }
+    let out = File::create(output).unwrap_or_else(|e| fail(&format!("Error creating {}: {}", output, e)));
+    model.write(BufWriter::new(out)).unwrap_or_else(|e| fail(&format!("Error writing {}: {}", output, e)));
+    println!("Wrote {} words and {} bigrams to {}", model.table.len(), model.bigrams.len(), output);
 }
 
-fn find_deletions(word: String) -> Edits {
-    let mut edits = Edits::new();
-
-    if word.len() <= 1 {
-        return edits
-    }
-
-    for i in 0..word.len() as usize {
-        let deleted : String = {
-            if i == 0 {
-                (&word[i + (1 as usize)..]).to_string()
-            } else if i == word.len() - 1 {
-                (&word[..i]).to_string()
-            } else {
-                (&word[..i]).to_string() + &word[i + (1 as usize)..]
-            }
-        };
-        edits.insert(deleted.clone());
+/// `path:weight`, or just `path` with a weight of 1
+fn weighted_source(source: &str) -> (&str, f64) {
+    match source.rsplit_once(':') {
+        Some((path, weight)) => match weight.parse::<f64>() {
+            Ok(weight) if weight >= 0.0 => (path, weight),
+            _ => (source, 1.0),
+        },
+        None => (source, 1.0),
     }
-    edits
 }
 
-
-fn find_replacements(word: String) -> Edits {
-    let mut edits = Edits::new(); 
-    let mut replaced : String;
-
-    for i in 0..word.len() as usize {
-        for c in ALPHABETS.chars() {
-            replaced = (&word[..i]).to_string();
-            replaced.push(c);
-            replaced = replaced + &word[i + (1 as usize)..];
-            edits.insert(replaced.clone());
-        }
-    }
-    edits
+#[derive(PartialEq)]
+enum Mode {
+    Lines,
+    Text,
+    Interactive,
+    Json,
+    Complete,
+    Serve,
+    Eval,
+    Source,
 }
 
-fn find_insertions(word: String) -> Edits {
-    let mut edits = Edits::new();
-    let mut inserted : String;
+/// `correct add --dict words.txt word...`
+fn add_words(args: Vec<String>) {
+    if args.len() < 3 || args[0] != "--dict" {
+        panic!("{}", USAGE);
+    }
 
-    for i in 0..word.len() + 1 as usize {
-        for c in ALPHABETS.chars() {
-            inserted = (&word[..i]).to_string();
-            inserted.push(c);
-            inserted = inserted + &word[i..];
-            edits.insert(inserted.clone());
+    let mut dictionary = PersonalDictionary::open(&args[1])
+        .unwrap_or_else(|e| fail(&format!("Error loading {}: {}", args[1], e)));
+    for word in &args[2..] {
+        match dictionary.add(word) {
+            Ok(true) => println!("added {}", word),
+            Ok(false) => println!("{} is already in {}", word, args[1]),
+            Err(e) => fail(&format!("Error writing {}: {}", args[1], e)),
         }
     }
-    edits
 }
 
-fn find_transpositions(word: String) -> Edits {
-    let mut edits = Edits::new();
-    let mut transposed: String;
-    let mut characters: Vec<char> = vec![];
-
-    for c in word.chars() {
-        characters.push(c);
-    }
-
-    for i in 0..word.len() - 1 as usize {
-        transposed = (&word[..i]).to_string();
-        transposed.push(characters[i + (1 as usize)]);
-        transposed.push(characters[i]);
-        transposed = transposed + &word[i + (2 as usize)..];
-        edits.insert(transposed.clone());
-    }
-    edits
+struct Options {
+    train: String,
+    errors: Option<String>,
+    dict: Option<String>,
+    ignore: Option<String>,
+    hunspell: Option<String>,
+    min_confidence: Option<f64>,
+    phonetic: Option<Algorithm>,
+    phonetic_weight: Option<f64>,
+    bktree: bool,
+    max_distance: Option<usize>,
+    threads: Option<usize>,
+    mode: Mode,
+    annotate: bool,
+    context: bool,
+    fuzzy: bool,
+    suggestions: usize,
+    address: Option<String>,
+    pairs: Option<String>,
+    files: Vec<String>,
+    identifiers: bool,
 }
 
-fn parse_input(input: String) -> String {
-    // These should get deleted from original string
-    let chars_to_trim = &["!", "?", "\"", "'"]; 
-    // These should simply be replaced by space, otherwise "hello,world" becomes "helloworld"
-    let chars_to_replace = &[",", "."]; 
-    let mut result = input.to_lowercase();
-    for character in chars_to_trim {
-        result = result.replace(character, "");
-    }
-    for character in chars_to_replace {
-        result = result.replace(character, " ");
+/// Removes `flag value` from the arguments, returning the value
+fn take_option(args: &mut Vec<String>, flag: &str) -> Option<String> {
+    let i = args.iter().position(|a| a == flag)?;
+    if i + 1 >= args.len() {
+        panic!("{}", USAGE);
     }
-    result
+    let value = args.remove(i + 1);
+    args.remove(i);
+    Some(value)
 }
 
-
-fn form_table<R: Read>(reader: R) -> CountTable {
-    let mut lines = BufReader::new(reader).lines();
-    let mut table = CountTable::new();
-
-    while let Some(Ok(line)) = lines.next() {
-        let replaced_line = parse_input(line.to_owned());
-        let words: Vec<&str> = replaced_line.split_whitespace().collect();
-        for word in words {
-            increment_word(&mut table, word.to_owned());
-        }
+/// `correct serve model.bin [--addr host:port] ...`, the other options as for checking
+fn parse_serve_args(mut args: Vec<String>) -> Options {
+    let address = take_option(&mut args, "--addr");
+    let options = parse_args(args);
+    if options.mode != Mode::Lines {
+        panic!("{}", USAGE);
     }
-    return table
-}
-
-
-#[allow(dead_code)]
-fn increment_word(map: &mut CountTable, word: String) {
-    *map.entry(word).or_insert(0) += 1;
+    Options { mode: Mode::Serve, address, ..options }
 }
 
-#[cfg(test)]
-mod form_table_tests {
-    use super::{CountTable, form_table};
-    use std::io::{Read, Result};
-
-    #[test]
-    fn form_table_test_basic_1() {
-        let table = make_test_table("Hello, world!");
-        assert_saved(&table, "world", 1);
-        assert_saved(&table, "hello", 1);
-        assert_none(&table, "hellooo");
+/// `correct eval pairs.txt model.bin [-n suggestions] ...`
+fn parse_eval_args(mut args: Vec<String>) -> Options {
+    if args.is_empty() {
+        panic!("{}", USAGE);
     }
-
-    #[test]
-    fn form_table_test_basic_2() {
-        let table = make_test_table("Of the dogs, By the dogs, For the dogs");
-        assert_saved(&table, "dogs", 3);
-        assert_saved(&table, "the", 3);
-        assert_saved(&table, "of", 1);
-        assert_none(&table, "people");
+    let pairs = args.remove(0);
+    let suggestions = take_option(&mut args, "-n").map(|n| n.parse().expect(USAGE));
+    let options = parse_args(args);
+    if options.mode != Mode::Lines {
+        panic!("{}", USAGE);
     }
-
-    #[test]
-    fn form_table_test_empty() {
-        let table = make_test_table("");
-        assert_none(&table, "Hi");
-    }
-
-    fn make_test_table(input: &str) -> CountTable {
-        let mock_read = StringReader::new(input.to_owned());
-        form_table(mock_read)
+    Options {
+        mode: Mode::Eval,
+        pairs: Some(pairs),
+        suggestions: suggestions.unwrap_or(DEFAULT_SUGGESTIONS),
+        ..options
     }
+}
 
-    fn assert_saved(table: &CountTable, test: &str, expected_num: usize) {
-        assert_eq!(Some(&expected_num), table.get(test));
+/// Suggestions shown per misspelling by `correct source`
+const SOURCE_SUGGESTIONS: usize = 3;
+
+/// Options of the checking modes that take a value
+static VALUE_OPTIONS: [&str; 10] = ["--errors", "--dict", "--ignore", "--hunspell", "--min-confidence", "--phonetic",
+                                    "--phonetic-weight", "--search", "--max-distance", "--threads"];
+
+/// `correct source model.bin file... [-n suggestions] [--no-identifiers] ...`
+fn parse_source_args(mut args: Vec<String>) -> Options {
+    let suggestions = take_option(&mut args, "-n").map(|n| n.parse().expect(USAGE));
+    let identifiers = match args.iter().position(|a| a == "--no-identifiers") {
+        Some(i) => { args.remove(i); false }
+        None => true,
+    };
+    // The first argument that isn't an option or its value is the model, the others are files
+    let mut rest = Vec::new();
+    let mut files = Vec::new();
+    let mut model_seen = false;
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        if VALUE_OPTIONS.contains(&arg.as_str()) {
+            rest.push(arg);
+            rest.extend(args.next());
+        } else if arg.starts_with('-') {
+            rest.push(arg);
+        } else if !model_seen {
+            model_seen = true;
+            rest.push(arg);
+        } else {
+            files.push(arg);
+        }
     }
 
-    fn assert_none(table: &CountTable, test: &str) {
-        assert_eq!(None, table.get(test));
+    let options = parse_args(rest);
+    if options.mode != Mode::Lines || options.threads.is_some() || files.is_empty() {
+        panic!("{}", USAGE);
     }
-
-    struct StringReader {
-        contents: Vec<u8>,
-        position: usize,
+    Options {
+        mode: Mode::Source,
+        files,
+        identifiers,
+        suggestions: suggestions.unwrap_or(SOURCE_SUGGESTIONS),
+        ..options
     }
+}
 
-    impl StringReader {
-        fn new(s: String) -> Self {
-            StringReader {
-                contents: s.into_bytes(),
-                position: 0,
+fn parse_args(args: Vec<String>) -> Options {
+    let mut train = None;
+    let mut errors = None;
+    let mut dict = None;
+    let mut ignore = None;
+    let mut hunspell = None;
+    let mut min_confidence = None;
+    let mut phonetic = None;
+    let mut phonetic_weight = None;
+    let mut bktree = false;
+    let mut max_distance = None;
+    let mut threads = None;
+    let mut mode = Mode::Lines;
+    let mut annotate = false;
+    let mut context = false;
+    let mut fuzzy = false;
+    let mut suggestions = None;
+    let mut args = args.into_iter();
+
+    while let Some(arg) = args.next() {
+        let new_mode = match arg.as_str() {
+            "--errors" => { errors = Some(args.next().expect(USAGE)); None }
+            "--dict" => { dict = Some(args.next().expect(USAGE)); None }
+            "--ignore" => { ignore = Some(args.next().expect(USAGE)); None }
+            "--hunspell" => { hunspell = Some(args.next().expect(USAGE)); None }
+            "--min-confidence" => {
+                min_confidence = Some(args.next().and_then(|c| c.parse().ok()).expect(USAGE));
+                None
             }
-        }
-    }
-
-    impl Read for StringReader {
-        fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
-            let mut count = 0;
-
-            while self.position < self.contents.len() && count < buf.len() {
-                buf[count] = self.contents[self.position];
-                count += 1;
-                self.position += 1;
+            "--phonetic" => { phonetic = Some(args.next().and_then(|a| Algorithm::from_name(&a)).expect(USAGE)); None }
+            "--search" => {
+                bktree = match args.next().as_deref() {
+                    Some("edits") => false,
+                    Some("bktree") => true,
+                    _ => panic!("{}", USAGE),
+                };
+                None
+            }
+            "--threads" => { threads = Some(args.next().and_then(|n| n.parse().ok()).expect(USAGE)); None }
+            "--max-distance" => { max_distance = Some(args.next().and_then(|n| n.parse().ok()).expect(USAGE)); None }
+            "--phonetic-weight" => {
+                phonetic_weight = Some(args.next().and_then(|w| w.parse().ok()).expect(USAGE));
+                None
             }
+            "--text" => Some(Mode::Text),
+            "--interactive" => Some(Mode::Interactive),
+            "--json" => Some(Mode::Json),
+            "--complete" => Some(Mode::Complete),
+            "--fuzzy" => { fuzzy = true; None }
+            "--annotate" => { annotate = true; None }
+            "--context" => { context = true; None }
+            "-n" => { suggestions = Some(args.next().and_then(|n| n.parse().ok()).expect(USAGE)); None }
+            _ if train.is_none() && !arg.starts_with('-') => { train = Some(arg); None }
+            _ => panic!("{}", USAGE),
+        };
 
-            return Ok(count);
+        if let Some(new_mode) = new_mode {
+            if mode != Mode::Lines {
+                panic!("{}", USAGE);
+            }
+            mode = new_mode;
         }
     }
-}
-
-#[cfg(test)]
-mod parse_input_tests {
-    use super::{parse_input};
-
-    #[test]
-    fn parse_input_strip_test() {
-        assert_parse_eq("hello!", "hello");
-        assert_parse_eq("hello!?!?!", "hello");
-        assert_parse_eq("Hello\"", "hello");
-    }
 
-    #[test]
-    fn parse_input_replace_test() {
-        assert_parse_eq("hello,world", "hello world");
-        assert_parse_eq("hello.world", "hello world");
-    }
-
-    #[test]
-    fn parse_input_lower_test() {
-        assert_parse_eq("HELLO", "hello");
-        assert_parse_eq("hello", "hello");
-        assert_parse_eq("HelLo", "hello");
-    }
-
-    fn assert_parse_eq(string: &str, result: &str) {
-        assert_eq!(result.to_owned(), parse_input(string.to_owned()));
+    if ((annotate || context) && mode != Mode::Text) || (suggestions.is_some() && mode != Mode::Interactive && mode != Mode::Complete)
+        || (fuzzy && mode != Mode::Complete)
+        || (max_distance.is_some() && !bktree)
+        || (threads.is_some() && mode != Mode::Lines && mode != Mode::Json)
+        || (min_confidence.is_some() && (context || mode == Mode::Complete)) {
+        panic!("{}", USAGE);
+    }
+
+    Options {
+        train: train.expect(USAGE),
+        errors,
+        dict,
+        ignore,
+        hunspell,
+        min_confidence,
+        phonetic,
+        phonetic_weight,
+        bktree,
+        max_distance,
+        threads,
+        mode,
+        annotate,
+        context,
+        fuzzy,
+        suggestions: suggestions.unwrap_or(DEFAULT_SUGGESTIONS),
+        address: None,
+        pairs: None,
+        files: Vec::new(),
+        identifiers: true,
     }
 }
 
+fn correct<R: Read>(reader: R, corrector: &Corrector) {
+    let mut lines = BufReader::new(reader).lines();
 
-#[cfg(test)]
-mod increment_word_tests {
-    use super::{increment_word, CountTable};
-
-    #[test]
-    fn inserts_if_empty() {
-        let mut h = CountTable::new();
-        increment_word(&mut h, "one".to_owned());
-
-        assert_eq!(Some(&1), h.get("one"));
-        assert_eq!(1, h.len());
-    }
-
-    #[test]
-    fn increments_if_present() {
-        let mut under_test = fixture();
-        let mut expected   = fixture();
-
-        increment_word(&mut under_test, "three".to_owned());
-        expected.insert("three".to_owned(), 4);
-
-        assert_eq!(expected, under_test);
-    }
-
-    #[test]
-    fn insert_if_absent() {
-        let mut under_test = fixture();
-        let mut expected   = fixture();
-
-        increment_word(&mut under_test, "one".to_owned());
-        expected.insert("one".to_owned(), 1);
-
-        assert_eq!(expected, under_test);
-    }
-
-    fn fixture() -> CountTable {
-        let mut h = CountTable::new();
-        h.insert("two".to_owned(), 2);
-        h.insert("three".to_owned(), 3);
-
-        assert_eq!(None, h.get("one"));
-        assert_eq!(Some(&2), h.get("two"));
-        assert_eq!(Some(&3), h.get("three"));
-        assert_eq!(2, h.len());
-        h
+    while let Some(Ok(word)) = lines.next() {
+        println!("{}", line_result(&word, corrector));
     }
 }
 
-#[cfg(test)]
-mod edits_test {
-    use super::{find_transpositions, find_deletions, find_replacements, find_insertions, edits_one, edits_two};
-
-    #[test]
-    fn find_transpositions_test() {
-        let trans_1 = find_transpositions("ab".to_owned());
-        assert!(trans_1.contains("ba"));
-
-        let trans_2 = find_transpositions("abc".to_owned());
-        assert!(trans_2.contains("bac"));
-        assert!(trans_2.contains("acb"));
-        assert!(!trans_2.contains("abcd"));
-    }
-
-    #[test]
-    fn find_deletions_test() {
-        let dels = find_deletions("ab".to_owned());
-        assert!(dels.contains("b"));
-        assert!(dels.contains("a"));
-        assert!(!dels.contains("ab"));
-
-        let dels2 = find_deletions("abcd".to_owned());
-        assert!(dels2.contains("abc"));
-        assert!(dels2.contains("bcd"));
-        assert!(dels2.contains("acd"));
-        assert!(dels2.contains("abd"));
-
-    }
+/// Prints "prefix: completion completion ...", or "prefix: -" when nothing starts with it
+fn complete<R: Read>(reader: R, trie: &Trie, n: usize, fuzzy: bool) {
+    let mut lines = BufReader::new(reader).lines();
 
-    #[test]
-    fn find_replacements_test() {
-        let reps = find_replacements("abc".to_owned());
-        assert!(reps.contains("abd"));
-        assert!(reps.contains("dbc"));
-        assert!(reps.contains("acc"));
-
-        assert!(!reps.contains("accd"));
-        assert!(!reps.contains("cd"));
-        assert!(!reps.contains("bcd"));
-        assert!(!reps.contains("acd"));
+    while let Some(Ok(prefix)) = lines.next() {
+        let completions = if fuzzy { trie.complete_fuzzy(&prefix, n) } else { trie.complete(&prefix, n) };
+        let words: Vec<&str> = completions.iter().map(|c| c.word).collect();
+        println!("{}: {}", prefix, if words.is_empty() { "-".to_owned() } else { words.join(" ") });
     }
+}
 
+/// Prints "path:line:column: word -> suggestion, ..." for every misspelling in the files.
+/// @return bool - whether the files are clean
+fn check_sources(paths: &[String], corrector: &Corrector, n: usize, identifiers: bool) -> bool {
+    let mut clean = true;
+    for path in paths {
+        let text = fs::read_to_string(path).unwrap_or_else(|e| fail(&format!("Error loading {}: {}", path, e)));
+        let extension = Path::new(path).extension().and_then(|e| e.to_str()).unwrap_or("");
+        let syntax = Syntax::from_extension(extension);
+        let found = if identifiers {
+            code_misspellings(&text, syntax, corrector)
+        } else {
+            misspellings(&text, syntax, corrector)
+        };
 
-    #[test]
-    fn find_insertions_test() {
-        let ins = find_insertions("ab".to_owned());
-        assert!(ins.contains("abc"));
-        assert!(ins.contains("abb"));
-        assert!(ins.contains("abd"));
-
-        assert!(!ins.contains("acc"));
-        assert!(!ins.contains("abcd"));
-    }
-
-    #[test]
-    fn edit1_test() {
-        let edit1s = edits_one(&"abcd".to_owned());
-        assert!(edit1s.contains("abd"));
-        assert!(edit1s.contains("abcde"));
-        assert!(edit1s.contains("abcz"));
-        assert!(edit1s.contains("bacd"));
-
-        assert!(!edit1s.contains("adzcd"));
-        assert!(!edit1s.contains("aabed"));
-        assert!(!edit1s.contains("cccd"));
-        assert!(!edit1s.contains("cd"));
+        let index = LineIndex::new(&text);
+        for misspelling in &found {
+            let (line, column) = index.position(misspelling.offset);
+            let suggestions: Vec<String> = misspelling.suggestions.iter().take(n)
+                .map(|s| misspelling.replacement(s))
+                .collect();
+            let suggestions = if suggestions.is_empty() { "-".to_owned() } else { suggestions.join(", ") };
+            println!("{}:{}:{}: {} -> {}", path, line + 1, column + 1, misspelling.word, suggestions);
+        }
+        clean &= found.is_empty();
     }
+    clean
+}
 
-    #[test]
-    fn edit2_test() {
-        let edit2s = edits_two(&"abcd".to_owned());
-        assert!(edit2s.contains("aabcd"));
-        assert!(edit2s.contains("aabed"));
-        assert!(edit2s.contains("badc"));
-        assert!(edit2s.contains("abcdef"));
-        assert!(edit2s.contains("cd"));
-
-        assert!(!edit2s.contains("d"));
-        assert!(!edit2s.contains("abcdefg"));
-        assert!(!edit2s.contains("cqfg"));
+fn correct_prose<R: Read>(mut reader: R, corrector: &Corrector, context: Option<&ContextModel>,
+                          annotate: bool) {
+    let mut input = String::new();
+    reader.read_to_string(&mut input).expect("Error reading input text ");
+
+    let (corrected, changes) = match context {
+        Some(context) => correct_text_in_context(&input, corrector, context),
+        None => correct_text(&input, corrector),
+    };
+    if annotate {
+        for change in changes {
+            println!("{}", change);
+        }
+    } else {
+        print!("{}", corrected);
     }
 }
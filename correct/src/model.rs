@@ -0,0 +1,522 @@
+#![doc="
+    Serialized dictionary model, so a large corpus only has to be trained on once.
+
+    Layout (integers little endian, `varint` is unsigned LEB128):
+
+    ```text
+    magic       8 bytes   CORRECT\\0
+    version     u32       FORMAT_VERSION
+    length      u64       payload length in bytes
+    checksum    u64       FNV-1a hash of the payload
+    payload:
+        words   varint n, then n times (varint byte length, UTF-8 bytes, varint count),
+                sorted, so a word's position is its id
+        bigrams varint m, then m times (varint first id, varint second id, varint count)
+        phonetic  varint byte length and name of the key (0 for no index), then varint k and
+                  k times (varint key length, key bytes, varint j, j word ids), sorted by key
+    ```
+
+    Version 1 files, without the phonetic section, are still read.
+
+    Loading checks the magic, version, length and checksum before decoding, and reports which
+    one failed instead of handing back a half-read model.
+
+    A model can be trained on several corpora, each with a weight its counts are multiplied by,
+    then pruned of words seen fewer than a minimum number of times: a typo in the corpus would
+    otherwise become a known word. The weighted counts stay fractional until pruning, so only
+    pruning drops words; the counts kept are then rounded, but never below 1.
+"]
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, Read, Write};
+use std::path::Path;
+use std::str;
+
+use phonetic::{Algorithm, PhoneticIndex};
+use table::{form_tables, BigramTable, CountTable};
+
+pub const MAGIC: &[u8; 8] = b"CORRECT\0";
+pub const FORMAT_VERSION: u32 = 2;
+/// The last version without a phonetic section
+const VERSION_WITHOUT_PHONETIC: u32 = 1;
+const HEADER_LEN: usize = 8 + 4 + 8 + 8;
+
+/// Everything the training phase produces
+#[derive(Debug, Default, PartialEq)]
+pub struct Model {
+    pub table: CountTable,
+    pub bigrams: BigramTable,
+    /// Built by `correct train --phonetic`, over `table`
+    pub phonetic: Option<PhoneticIndex>,
+}
+
+#[derive(Debug)]
+pub enum ModelError {
+    Io(io::Error),
+    NotAModel,
+    Version { found: u32, expected: u32 },
+    Truncated { found: usize, expected: usize },
+    Checksum { found: u64, expected: u64 },
+    Corrupt(&'static str),
+}
+
+impl fmt::Display for ModelError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ModelError::Io(ref e) => write!(f, "could not read model: {}", e),
+            ModelError::NotAModel => write!(f, "not a correct model file (bad magic number)"),
+            ModelError::Version { found, expected } =>
+                write!(f, "model format version {} is not supported (expected {}), retrain it with `correct train`",
+                       found, expected),
+            ModelError::Truncated { found, expected } =>
+                write!(f, "model is truncated: payload is {} bytes, header says {}", found, expected),
+            ModelError::Checksum { found, expected } =>
+                write!(f, "model checksum mismatch: computed {:016x}, header says {:016x}", found, expected),
+            ModelError::Corrupt(what) => write!(f, "model is corrupt: {}", what),
+        }
+    }
+}
+
+impl Error for ModelError {}
+
+impl From<io::Error> for ModelError {
+    fn from(e: io::Error) -> ModelError {
+        ModelError::Io(e)
+    }
+}
+
+impl Model {
+    pub fn train<R: Read>(reader: R) -> Model {
+        let (table, bigrams) = form_tables(reader);
+        Model { table, bigrams, phonetic: None }
+    }
+
+    /// Loads a model file, or trains on the file if it is plain text
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Model, ModelError> {
+        let mut bytes = Vec::new();
+        File::open(path)?.read_to_end(&mut bytes)?;
+
+        if bytes.starts_with(MAGIC) {
+            Model::decode(&bytes)
+        } else {
+            Ok(Model::train(&bytes[..]))
+        }
+    }
+
+    pub fn read<R: Read>(mut reader: R) -> Result<Model, ModelError> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes)?;
+        Model::decode(&bytes)
+    }
+
+    pub fn write<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        let payload = self.encode_payload();
+
+        writer.write_all(MAGIC)?;
+        writer.write_all(&FORMAT_VERSION.to_le_bytes())?;
+        writer.write_all(&(payload.len() as u64).to_le_bytes())?;
+        writer.write_all(&checksum(&payload).to_le_bytes())?;
+        writer.write_all(&payload)?;
+        writer.flush()
+    }
+
+    fn encode_payload(&self) -> Vec<u8> {
+        let mut words: Vec<(&String, &usize)> = self.table.iter().collect();
+        words.sort();
+        let ids: HashMap<&String, usize> = words.iter().enumerate().map(|(id, &(w, _))| (w, id)).collect();
+
+        let mut out = Vec::new();
+        put_varint(&mut out, words.len() as u64);
+        for (word, count) in words {
+            put_varint(&mut out, word.len() as u64);
+            out.extend_from_slice(word.as_bytes());
+            put_varint(&mut out, *count as u64);
+        }
+
+        let mut bigrams: Vec<(usize, usize, usize)> = self.bigrams.iter()
+            .filter_map(|((a, b), &count)| match (ids.get(a), ids.get(b)) {
+                (Some(&a), Some(&b)) => Some((a, b, count)),
+                _ => None,
+            })
+            .collect();
+        bigrams.sort();
+        put_varint(&mut out, bigrams.len() as u64);
+        for (a, b, count) in bigrams {
+            put_varint(&mut out, a as u64);
+            put_varint(&mut out, b as u64);
+            put_varint(&mut out, count as u64);
+        }
+
+        let name = self.phonetic.as_ref().map_or("", |index| index.algorithm().name());
+        put_varint(&mut out, name.len() as u64);
+        out.extend_from_slice(name.as_bytes());
+        if let Some(ref index) = self.phonetic {
+            let mut groups: Vec<(&String, Vec<usize>)> = index.groups()
+                .map(|(key, group)| (key, group.iter().filter_map(|w| ids.get(w).cloned()).collect()))
+                .collect();
+            groups.sort();
+            put_varint(&mut out, groups.len() as u64);
+            for (key, group) in groups {
+                put_varint(&mut out, key.len() as u64);
+                out.extend_from_slice(key.as_bytes());
+                put_varint(&mut out, group.len() as u64);
+                for id in group {
+                    put_varint(&mut out, id as u64);
+                }
+            }
+        }
+        out
+    }
+
+    fn decode(bytes: &[u8]) -> Result<Model, ModelError> {
+        if !bytes.starts_with(MAGIC) {
+            return Err(ModelError::NotAModel);
+        }
+        if bytes.len() < HEADER_LEN {
+            return Err(ModelError::Truncated { found: bytes.len(), expected: HEADER_LEN });
+        }
+
+        let version = u32::from_le_bytes([bytes[8], bytes[9], bytes[10], bytes[11]]);
+        if version != FORMAT_VERSION && version != VERSION_WITHOUT_PHONETIC {
+            return Err(ModelError::Version { found: version, expected: FORMAT_VERSION });
+        }
+
+        let length = read_u64(&bytes[12..20]) as usize;
+        let expected = read_u64(&bytes[20..28]);
+        let payload = &bytes[HEADER_LEN..];
+        if payload.len() != length {
+            return Err(ModelError::Truncated { found: payload.len(), expected: length });
+        }
+        let found = checksum(payload);
+        if found != expected {
+            return Err(ModelError::Checksum { found, expected });
+        }
+
+        Model::decode_payload(payload, version)
+    }
+
+    fn decode_payload(payload: &[u8], version: u32) -> Result<Model, ModelError> {
+        let mut cursor = Cursor { bytes: payload, position: 0 };
+        let mut model = Model::default();
+
+        let word_count = cursor.varint()? as usize;
+        // The count comes from the file; every word takes at least a byte, so a corrupt one
+        // can't ask for more room than that
+        let mut words = Vec::with_capacity(word_count.min(payload.len()));
+        for _ in 0..word_count {
+            let len = cursor.varint()? as usize;
+            let word = String::from_utf8(cursor.take(len)?.to_vec())
+                .map_err(|_| ModelError::Corrupt("word is not valid UTF-8"))?;
+            let count = cursor.varint()? as usize;
+            model.table.insert(word.clone(), count);
+            words.push(word);
+        }
+
+        let bigram_count = cursor.varint()? as usize;
+        for _ in 0..bigram_count {
+            let a = cursor.varint()? as usize;
+            let b = cursor.varint()? as usize;
+            let count = cursor.varint()? as usize;
+            match (words.get(a), words.get(b)) {
+                (Some(a), Some(b)) => model.bigrams.insert((a.clone(), b.clone()), count),
+                _ => return Err(ModelError::Corrupt("bigram refers to an unknown word")),
+            };
+        }
+
+        if version != VERSION_WITHOUT_PHONETIC {
+            model.phonetic = decode_phonetic(&mut cursor, &words)?;
+        }
+
+        if cursor.position != payload.len() {
+            return Err(ModelError::Corrupt("trailing bytes after the model"));
+        }
+        Ok(model)
+    }
+}
+
+fn decode_phonetic(cursor: &mut Cursor, words: &[String]) -> Result<Option<PhoneticIndex>, ModelError> {
+    let len = cursor.varint()? as usize;
+    if len == 0 {
+        return Ok(None);
+    }
+    let algorithm = str::from_utf8(cursor.take(len)?).ok().and_then(Algorithm::from_name)
+        .ok_or(ModelError::Corrupt("unknown phonetic key"))?;
+
+    let mut groups = HashMap::new();
+    for _ in 0..cursor.varint()? {
+        let len = cursor.varint()? as usize;
+        let key = String::from_utf8(cursor.take(len)?.to_vec())
+            .map_err(|_| ModelError::Corrupt("phonetic key is not valid UTF-8"))?;
+        let mut group = Vec::new();
+        for _ in 0..cursor.varint()? {
+            let word = words.get(cursor.varint()? as usize)
+                .ok_or(ModelError::Corrupt("phonetic key refers to an unknown word"))?;
+            group.push(word.clone());
+        }
+        groups.insert(key, group);
+    }
+    Ok(Some(PhoneticIndex::from_groups(algorithm, groups)))
+}
+
+/// Counts of several models, each multiplied by a weight, kept fractional until `finish`
+#[derive(Default)]
+pub struct WeightedSum {
+    table: HashMap<String, f64>,
+    bigrams: HashMap<(String, String), f64>,
+}
+
+impl WeightedSum {
+    /// Adds the counts of a model, multiplied by `weight`. Its phonetic index isn't kept.
+    pub fn add(&mut self, model: Model, weight: f64) {
+        for (word, count) in model.table {
+            *self.table.entry(word).or_insert(0.0) += count as f64 * weight;
+        }
+        for (pair, count) in model.bigrams {
+            *self.bigrams.entry(pair).or_insert(0.0) += count as f64 * weight;
+        }
+    }
+
+    /// The summed model without the words counted fewer than `min_count` times (or not at all,
+    /// with a weight of 0), and the bigrams they are part of. Kept counts are rounded, but
+    /// never below 1.
+    /// @return (Model, usize) - the model and the number of words dropped
+    pub fn finish(self, min_count: usize) -> (Model, usize) {
+        let rounded = |count: f64| (count.round() as usize).max(1);
+        let before = self.table.len();
+        let table: CountTable = self.table.into_iter()
+            .filter(|&(_, count)| count > 0.0 && count >= min_count as f64)
+            .map(|(word, count)| (word, rounded(count)))
+            .collect();
+        let bigrams: BigramTable = self.bigrams.into_iter()
+            .filter(|&((ref first, ref second), count)| count > 0.0 && table.contains_key(first) && table.contains_key(second))
+            .map(|(pair, count)| (pair, rounded(count)))
+            .collect();
+        let dropped = before - table.len();
+        (Model { table, bigrams, phonetic: None }, dropped)
+    }
+}
+
+/// 64-bit FNV-1a
+pub fn checksum(bytes: &[u8]) -> u64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for &b in bytes {
+        hash ^= b as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    hash
+}
+
+fn read_u64(bytes: &[u8]) -> u64 {
+    let mut buf = [0u8; 8];
+    buf.copy_from_slice(bytes);
+    u64::from_le_bytes(buf)
+}
+
+fn put_varint(out: &mut Vec<u8>, mut value: u64) {
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            out.push(byte);
+            return;
+        }
+        out.push(byte | 0x80);
+    }
+}
+
+struct Cursor<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Cursor<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], ModelError> {
+        if len > self.bytes.len() - self.position {
+            return Err(ModelError::Corrupt("unexpected end of payload"));
+        }
+        let slice = &self.bytes[self.position..self.position + len];
+        self.position += len;
+        Ok(slice)
+    }
+
+    fn varint(&mut self) -> Result<u64, ModelError> {
+        let mut value = 0u64;
+        for shift in 0..10 {
+            let byte = self.take(1)?[0];
+            value |= ((byte & 0x7f) as u64) << (7 * shift);
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(ModelError::Corrupt("varint is too long"))
+    }
+}
+
+#[cfg(test)]
+mod model_tests {
+    use super::{checksum, put_varint, Model, ModelError, WeightedSum, FORMAT_VERSION, HEADER_LEN};
+    use phonetic::{Algorithm, PhoneticIndex};
+
+    fn sample() -> Model {
+        Model::train("The quick brown fox, the lazy dog. Déjà vu!".as_bytes())
+    }
+
+    fn written(model: &Model) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        model.write(&mut bytes).unwrap();
+        bytes
+    }
+
+    #[test]
+    fn round_trip() {
+        let model = sample();
+        let loaded = Model::read(&written(&model)[..]).unwrap();
+        assert_eq!(loaded, model);
+        assert_eq!(Some(&2), loaded.table.get("the"));
+        assert_eq!(Some(&1), loaded.bigrams.get(&("déjà".to_owned(), "vu".to_owned())));
+    }
+
+    #[test]
+    fn output_is_deterministic() {
+        assert_eq!(written(&sample()), written(&sample()));
+    }
+
+    #[test]
+    fn round_trip_with_phonetic_index() {
+        let mut model = Model::train("the phone rang at night and one knight answered".as_bytes());
+        model.phonetic = Some(PhoneticIndex::new(Algorithm::Metaphone, &model.table));
+        assert_eq!(written(&model), written(&model));
+
+        let loaded = Model::read(&written(&model)[..]).unwrap();
+        assert_eq!(loaded, model);
+        let index = loaded.phonetic.unwrap();
+        assert_eq!(index.algorithm(), Algorithm::Metaphone);
+        assert_eq!(index.matches("nite"), ["knight", "night"]);
+    }
+
+    #[test]
+    fn reads_version_1() {
+        // A version 1 payload is a version 2 payload without an index, minus the empty name
+        let model = sample();
+        let bytes = written(&model);
+        let payload = &bytes[HEADER_LEN..bytes.len() - 1];
+        let mut old = b"CORRECT\0".to_vec();
+        old.extend_from_slice(&1u32.to_le_bytes());
+        old.extend_from_slice(&(payload.len() as u64).to_le_bytes());
+        old.extend_from_slice(&checksum(payload).to_le_bytes());
+        old.extend_from_slice(payload);
+        assert_eq!(Model::read(&old[..]).unwrap(), model);
+    }
+
+    fn weighted_sum() -> WeightedSum {
+        let mut sum = WeightedSum::default();
+        sum.add(Model::train("the cat the cat the".as_bytes()), 1.0);
+        sum.add(Model::train("the dog".as_bytes()), 2.0);
+        sum.add(Model::train("the cta".as_bytes()), 0.4);
+        sum.add(Model::train("ignored".as_bytes()), 0.0);
+        sum
+    }
+
+    #[test]
+    fn merge_weights_counts() {
+        let (model, dropped) = weighted_sum().finish(0);
+        assert_eq!(model.table.get("the"), Some(&5));
+        assert_eq!(model.table.get("cat"), Some(&2));
+        assert_eq!(model.table.get("dog"), Some(&2));
+        // 0.4 would round to nothing; only pruning drops words
+        assert_eq!(model.table.get("cta"), Some(&1));
+        assert_eq!(model.table.get("ignored"), None);
+        assert_eq!(dropped, 1);
+        assert_eq!(model.bigrams.get(&("the".to_owned(), "dog".to_owned())), Some(&2));
+        assert_eq!(model.bigrams.get(&("cat".to_owned(), "the".to_owned())), Some(&2));
+        assert_eq!(model.bigrams.get(&("the".to_owned(), "cta".to_owned())), Some(&1));
+    }
+
+    #[test]
+    fn merge_prunes_fractional_counts() {
+        let (model, dropped) = weighted_sum().finish(2);
+        let mut words: Vec<&String> = model.table.keys().collect();
+        words.sort();
+        assert_eq!(words, vec!["cat", "dog", "the"]);
+        assert_eq!(dropped, 2);
+        assert_eq!(model.bigrams.get(&("the".to_owned(), "cta".to_owned())), None);
+    }
+
+
+    #[test]
+    fn empty_model() {
+        let model = Model::default();
+        assert_eq!(Model::read(&written(&model)[..]).unwrap(), model);
+    }
+
+    #[test]
+    fn rejects_text() {
+        match Model::read("hello world".as_bytes()) {
+            Err(ModelError::NotAModel) => (),
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn rejects_other_version() {
+        let mut bytes = written(&sample());
+        bytes[8] = 99;
+        match Model::read(&bytes[..]) {
+            Err(ModelError::Version { found: 99, expected }) => assert_eq!(expected, FORMAT_VERSION),
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn rejects_corrupted_payload() {
+        let mut bytes = written(&sample());
+        let last = bytes.len() - 1;
+        bytes[last] ^= 0xff;
+        match Model::read(&bytes[..]) {
+            Err(ModelError::Checksum { .. }) => (),
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn rejects_truncated_file() {
+        let bytes = written(&sample());
+        match Model::read(&bytes[..bytes.len() - 3]) {
+            Err(ModelError::Truncated { .. }) => (),
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn rejects_truncated_header() {
+        let bytes = written(&sample());
+        match Model::read(&bytes[..HEADER_LEN - 1]) {
+            Err(ModelError::Truncated { found, expected }) => assert_eq!((found, expected), (HEADER_LEN - 1, HEADER_LEN)),
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn rejects_huge_counts() {
+        // A word count and a word length far beyond the payload
+        for &(count, len) in &[(u64::MAX, 1), (1, u64::MAX)] {
+            let mut payload = Vec::new();
+            put_varint(&mut payload, count);
+            put_varint(&mut payload, len);
+            match Model::decode_payload(&payload, FORMAT_VERSION) {
+                Err(ModelError::Corrupt(_)) => (),
+                other => panic!("unexpected {:?}", other),
+            }
+        }
+    }
+
+    #[test]
+    fn error_messages() {
+        let message = ModelError::Version { found: 2, expected: 1 }.to_string();
+        assert!(message.contains("version 2"));
+        assert!(message.contains("retrain"));
+    }
+}
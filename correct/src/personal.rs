@@ -0,0 +1,223 @@
+#![doc="
+    Exceptions to correction. A personal dictionary holds words that are always accepted
+    (product names, jargon) and can grow from the command line or the interactive mode. An
+    ignore list holds regular expressions for things that aren't words at all: URLs, emails,
+    hex strings and CamelCase identifiers are ignored by default, more can be added from a file.
+    A hex string needs a decimal digit, or words made of the letters a to f would be ignored.
+
+    Both files have one entry per line; blank lines and lines starting with # are skipped.
+"]
+
+use std::collections::HashSet;
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::path::{Path, PathBuf};
+
+use regex::{self, Regex};
+
+pub static DEFAULT_IGNORE_PATTERNS: [&str; 5] = [
+    r"[a-zA-Z][a-zA-Z0-9+.-]*://\S+",               // URLs
+    r"www\.\S+",                                    // URLs without a scheme
+    r"[\w.+-]+@[\w-]+(\.[\w-]+)+",                  // emails
+    r"(0[xX][0-9a-fA-F]+|[0-9a-fA-F]*[0-9][0-9a-fA-F]*)", // hex strings
+    r"[A-Za-z]*[a-z][A-Z][A-Za-z0-9]*",             // CamelCase identifiers
+];
+
+#[derive(Default)]
+pub struct PersonalDictionary {
+    words: HashSet<String>,
+    path: Option<PathBuf>,
+}
+
+impl PersonalDictionary {
+    pub fn new() -> PersonalDictionary {
+        PersonalDictionary::default()
+    }
+
+    /// Loads the dictionary at `path`, which is created on the first `add` if missing
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<PersonalDictionary> {
+        let mut dictionary = match File::open(path.as_ref()) {
+            Ok(file) => PersonalDictionary::from_reader(file)?,
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => PersonalDictionary::new(),
+            Err(e) => return Err(e),
+        };
+        dictionary.path = Some(path.as_ref().to_owned());
+        Ok(dictionary)
+    }
+
+    pub fn from_reader<R: Read>(reader: R) -> io::Result<PersonalDictionary> {
+        let mut dictionary = PersonalDictionary::new();
+        for entry in entries(reader)? {
+            dictionary.words.insert(entry.to_lowercase());
+        }
+        Ok(dictionary)
+    }
+
+    /// Case-insensitive
+    pub fn contains(&self, word: &str) -> bool {
+        self.words.contains(&word.to_lowercase())
+    }
+
+    pub fn len(&self) -> usize {
+        self.words.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.words.is_empty()
+    }
+
+    /// Whether added words are written to a file, rather than kept for this session only
+    pub fn is_saved(&self) -> bool {
+        self.path.is_some()
+    }
+
+    /// Adds a word, appending it to the dictionary file if there is one.
+    /// @return bool - whether the word is new
+    pub fn add(&mut self, word: &str) -> io::Result<bool> {
+        if !self.words.insert(word.to_lowercase()) {
+            return Ok(false);
+        }
+        if let Some(ref path) = self.path {
+            let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+            writeln!(file, "{}", word)?;
+        }
+        Ok(true)
+    }
+}
+
+/// Empty by default; `defaults()` has the built-in patterns
+#[derive(Default)]
+pub struct IgnoreList {
+    /// (pattern, pattern anchored to match a whole word)
+    patterns: Vec<(Regex, Regex)>,
+}
+
+impl IgnoreList {
+    pub fn new() -> IgnoreList {
+        IgnoreList::default()
+    }
+
+    pub fn defaults() -> IgnoreList {
+        let mut list = IgnoreList::new();
+        for pattern in DEFAULT_IGNORE_PATTERNS.iter() {
+            list.add(pattern).expect("default ignore patterns are valid");
+        }
+        list
+    }
+
+    pub fn add(&mut self, pattern: &str) -> Result<(), regex::Error> {
+        let whole = Regex::new(&format!("^(?:{})$", pattern))?;
+        self.patterns.push((Regex::new(pattern)?, whole));
+        Ok(())
+    }
+
+    /// Adds every pattern in a file. Invalid patterns are reported with their line.
+    pub fn add_from<R: Read>(&mut self, reader: R) -> Result<(), String> {
+        for entry in entries(reader).map_err(|e| e.to_string())? {
+            self.add(&entry).map_err(|e| format!("bad ignore pattern {}: {}", entry, e))?;
+        }
+        Ok(())
+    }
+
+    /// Whether a pattern matches the whole word
+    pub fn matches(&self, word: &str) -> bool {
+        self.patterns.iter().any(|(_, whole)| whole.is_match(word))
+    }
+
+    /// Byte ranges of the text covered by any pattern
+    pub fn spans(&self, text: &str) -> Vec<(usize, usize)> {
+        let mut spans: Vec<(usize, usize)> = self.patterns.iter()
+            .flat_map(|(p, _)| p.find_iter(text).map(|m| (m.start(), m.end())))
+            .filter(|&(start, end)| end > start)
+            .collect();
+        spans.sort();
+        spans
+    }
+}
+
+/// Non-empty, non-comment lines, trimmed
+fn entries<R: Read>(reader: R) -> io::Result<Vec<String>> {
+    let mut found = Vec::new();
+    for line in BufReader::new(reader).lines() {
+        let line = line?;
+        let entry = line.trim();
+        if !entry.is_empty() && !entry.starts_with('#') {
+            found.push(entry.to_owned());
+        }
+    }
+    Ok(found)
+}
+
+#[cfg(test)]
+mod personal_tests {
+    use super::{IgnoreList, PersonalDictionary};
+    use std::env;
+    use std::fs;
+
+    #[test]
+    fn dictionary_from_reader() {
+        let dictionary = PersonalDictionary::from_reader("# products\nKubernetes\n\n  rustc \n".as_bytes()).unwrap();
+        assert_eq!(dictionary.len(), 2);
+        assert!(dictionary.contains("kubernetes"));
+        assert!(dictionary.contains("Kubernetes"));
+        assert!(dictionary.contains("rustc"));
+        assert!(!dictionary.contains("# products"));
+    }
+
+    #[test]
+    fn dictionary_add_appends_to_file() {
+        let path = env::temp_dir().join(format!("correct-personal-{}.txt", ::std::process::id()));
+        let _ = fs::remove_file(&path);
+
+        let mut dictionary = PersonalDictionary::open(&path).unwrap();
+        assert!(dictionary.is_empty());
+        assert!(dictionary.is_saved());
+        assert!(!PersonalDictionary::new().is_saved());
+        assert!(dictionary.add("Grafana").unwrap());
+        assert!(!dictionary.add("grafana").unwrap());
+        assert!(dictionary.add("tokio").unwrap());
+
+        let reloaded = PersonalDictionary::open(&path).unwrap();
+        assert!(reloaded.contains("grafana"));
+        assert!(reloaded.contains("tokio"));
+        assert_eq!(fs::read_to_string(&path).unwrap(), "Grafana\ntokio\n");
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn default_patterns() {
+        let ignore = IgnoreList::defaults();
+        assert!(ignore.matches("https://example.com/path?q=1"));
+        assert!(ignore.matches("www.example.com"));
+        assert!(ignore.matches("someone@example.co.uk"));
+        assert!(ignore.matches("0xdeadbeef"));
+        assert!(ignore.matches("3f2a9c1be07d"));
+        assert!(ignore.matches("HashMap"));
+        assert!(ignore.matches("camelCase"));
+
+        assert!(!ignore.matches("hello"));
+        assert!(!ignore.matches("Hello"));
+        assert!(!ignore.matches("HELLO"));
+        assert!(!ignore.matches("facade"));
+        assert!(!ignore.matches("fabaceae"));
+        assert!(!ignore.matches("Effaced"));
+    }
+
+    #[test]
+    fn custom_patterns() {
+        let mut ignore = IgnoreList::new();
+        ignore.add_from("# ticket ids\nJIRA-[0-9]+\n".as_bytes()).unwrap();
+        assert!(ignore.matches("JIRA-1234"));
+        assert!(!ignore.matches("see JIRA-1234"));
+        assert!(ignore.add_from("(unclosed".as_bytes()).is_err());
+        // Only defaults() has the built-in patterns
+        assert!(!IgnoreList::default().matches("HashMap"));
+    }
+
+    #[test]
+    fn spans_test() {
+        let ignore = IgnoreList::defaults();
+        let text = "mail me@example.com or see https://x.io now";
+        assert_eq!(ignore.spans(text), vec![(5, 19), (27, 39)]);
+    }
+}
@@ -0,0 +1,292 @@
+#![doc="
+    Sound-alike matching. Edit distance misses misspellings that are spelled the way they sound
+    (fone for phone, nite for night), so known words are also indexed by a phonetic key, and
+    words sharing the typed word's key become candidates too.
+
+    Two keys are available:
+        - Metaphone (Lawrence Philips' original rules): knows about PH, silent GH, KN, WR...
+        - Soundex: first letter and three digits for the consonant groups, coarser and keeps
+          the first letter, so it can't match fone with phone
+
+    Only the letters A to Z take part in a key; other letters are skipped.
+
+    `correct train --phonetic metaphone` builds the index at training time and stores it in
+    model.bin, so checking with that model finds sound-alikes without indexing again. A model
+    without an index, a different key or words added by --hunspell when checking get an index
+    built from the vocabulary at load time instead: one key per known word.
+"]
+
+use std::collections::HashMap;
+
+use table::CountTable;
+
+/// Default weight of a sound-alike candidate, relative to a candidate one edit away
+pub const DEFAULT_PHONETIC_WEIGHT: f64 = 1.0;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Algorithm {
+    Metaphone,
+    Soundex,
+}
+
+impl Algorithm {
+    pub fn from_name(name: &str) -> Option<Algorithm> {
+        match name {
+            "metaphone" => Some(Algorithm::Metaphone),
+            "soundex" => Some(Algorithm::Soundex),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Algorithm::Metaphone => "metaphone",
+            Algorithm::Soundex => "soundex",
+        }
+    }
+
+    pub fn key(&self, word: &str) -> String {
+        match *self {
+            Algorithm::Metaphone => metaphone(word),
+            Algorithm::Soundex => soundex(word),
+        }
+    }
+}
+
+/// Known words grouped by phonetic key
+#[derive(Debug, PartialEq)]
+pub struct PhoneticIndex {
+    algorithm: Algorithm,
+    words: HashMap<String, Vec<String>>,
+}
+
+impl PhoneticIndex {
+    pub fn new(algorithm: Algorithm, table: &CountTable) -> PhoneticIndex {
+        let mut words: HashMap<String, Vec<String>> = HashMap::new();
+        for word in table.keys() {
+            let key = algorithm.key(word);
+            if !key.is_empty() {
+                words.entry(key).or_default().push(word.clone());
+            }
+        }
+        PhoneticIndex::from_groups(algorithm, words)
+    }
+
+    /// Index of words already grouped by the key of `algorithm`, as stored in a model file
+    pub fn from_groups(algorithm: Algorithm, mut words: HashMap<String, Vec<String>>) -> PhoneticIndex {
+        for group in words.values_mut() {
+            group.sort();
+        }
+        PhoneticIndex { algorithm, words }
+    }
+
+    /// (key, words with that key) for every key, in no particular order
+    pub fn groups(&self) -> impl Iterator<Item = (&String, &Vec<String>)> {
+        self.words.iter()
+    }
+
+    pub fn algorithm(&self) -> Algorithm {
+        self.algorithm
+    }
+
+    /// Known words that sound like `word`, sorted
+    pub fn matches(&self, word: &str) -> &[String] {
+        self.words.get(&self.algorithm.key(word)).map_or(&[], |w| w.as_slice())
+    }
+}
+
+fn letters(word: &str) -> Vec<char> {
+    word.chars().filter(|c| c.is_ascii_alphabetic()).map(|c| c.to_ascii_uppercase()).collect()
+}
+
+fn is_vowel(c: Option<char>) -> bool {
+    match c {
+        Some(c) => "AEIOU".contains(c),
+        None => false,
+    }
+}
+
+pub fn metaphone(word: &str) -> String {
+    let mut w = letters(word);
+    // Initial letters that aren't pronounced, or are pronounced differently
+    if w.len() >= 2 {
+        match (w[0], w[1]) {
+            ('A', 'E') | ('G', 'N') | ('K', 'N') | ('P', 'N') | ('W', 'R') => { w.remove(0); }
+            ('W', 'H') => { w.remove(1); }
+            _ => (),
+        }
+    }
+    if w.first() == Some(&'X') {
+        w[0] = 'S';
+    }
+
+    let at = |i: usize| w.get(i).cloned();
+    let mut key = String::new();
+    for i in 0..w.len() {
+        let c = w[i];
+        let previous = if i > 0 { at(i - 1) } else { None };
+        let next = at(i + 1);
+        let after = at(i + 2);
+        // Doubled letters sound once, except CC (accent)
+        if previous == Some(c) && c != 'C' {
+            continue;
+        }
+
+        match c {
+            'A' | 'E' | 'I' | 'O' | 'U' => if i == 0 { key.push(c) },
+            'B' => if !(previous == Some('M') && next.is_none()) { key.push('B') },
+            'C' => {
+                if next == Some('I') && after == Some('A') || next == Some('H') && previous != Some('S') {
+                    key.push('X');
+                } else if next == Some('I') || next == Some('E') || next == Some('Y') {
+                    if previous != Some('S') {
+                        key.push('S');
+                    }
+                } else {
+                    key.push('K');
+                }
+            }
+            'D' => if next == Some('G') && (after == Some('E') || after == Some('Y') || after == Some('I')) {
+                key.push('J');
+            } else {
+                key.push('T');
+            },
+            'G' => {
+                let silent = (next == Some('H') && !is_vowel(after))
+                    || (next == Some('N') && (after.is_none() || w[i + 2..] == ['E', 'D']))
+                    || (previous == Some('D') && (next == Some('E') || next == Some('Y') || next == Some('I')));
+                if silent {
+                    continue;
+                }
+                if next == Some('I') || next == Some('E') || next == Some('Y') {
+                    key.push('J');
+                } else {
+                    key.push('K');
+                }
+            }
+            'H' => {
+                let after_modifier = previous.is_some_and(|p| "CSPTG".contains(p));
+                if !after_modifier && is_vowel(next) {
+                    key.push('H');
+                }
+            }
+            'K' => if previous != Some('C') { key.push('K') },
+            'P' => key.push(if next == Some('H') { 'F' } else { 'P' }),
+            'Q' => key.push('K'),
+            'S' => if next == Some('H') || next == Some('I') && (after == Some('O') || after == Some('A')) {
+                key.push('X');
+            } else {
+                key.push('S');
+            },
+            'T' => {
+                if next == Some('I') && (after == Some('O') || after == Some('A')) {
+                    key.push('X');
+                } else if next == Some('H') {
+                    key.push('0');
+                } else if !(next == Some('C') && after == Some('H')) {
+                    key.push('T');
+                }
+            }
+            'V' => key.push('F'),
+            'W' | 'Y' => if is_vowel(next) { key.push(c) },
+            'X' => key.push_str("KS"),
+            'Z' => key.push('S'),
+            _ => key.push(c),
+        }
+    }
+    key
+}
+
+pub fn soundex(word: &str) -> String {
+    let code = |c: char| match c {
+        'B' | 'F' | 'P' | 'V' => Some('1'),
+        'C' | 'G' | 'J' | 'K' | 'Q' | 'S' | 'X' | 'Z' => Some('2'),
+        'D' | 'T' => Some('3'),
+        'L' => Some('4'),
+        'M' | 'N' => Some('5'),
+        'R' => Some('6'),
+        _ => None,
+    };
+
+    let w = letters(word);
+    let first = match w.first() {
+        Some(&first) => first,
+        None => return String::new(),
+    };
+    let mut key = first.to_string();
+    let mut last = code(first);
+    for &c in &w[1..] {
+        let digit = code(c);
+        if let Some(d) = digit.filter(|_| digit != last) {
+            key.push(d);
+        }
+        // H and W don't separate letters with the same code, vowels do
+        if c != 'H' && c != 'W' {
+            last = digit;
+        }
+        if key.len() == 4 {
+            break;
+        }
+    }
+    while key.len() < 4 {
+        key.push('0');
+    }
+    key
+}
+
+#[cfg(test)]
+mod phonetic_tests {
+    use super::{metaphone, soundex, Algorithm, PhoneticIndex};
+    use table::form_table;
+
+    #[test]
+    fn metaphone_test() {
+        assert_eq!(metaphone("phone"), "FN");
+        assert_eq!(metaphone("fone"), "FN");
+        assert_eq!(metaphone("night"), "NT");
+        assert_eq!(metaphone("nite"), "NT");
+        assert_eq!(metaphone("knight"), "NT");
+        assert_eq!(metaphone("thumb"), "0M");
+        assert_eq!(metaphone("school"), "SKL");
+        assert_eq!(metaphone("science"), "SNS");
+        assert_eq!(metaphone("judge"), "JJ");
+        assert_eq!(metaphone("nation"), "NXN");
+        assert_eq!(metaphone("wright"), "RT");
+        assert_eq!(metaphone("xylophone"), "SLFN");
+        assert_eq!(metaphone("apple"), "APL");
+        assert_eq!(metaphone("café"), "KF");
+        assert_eq!(metaphone(""), "");
+    }
+
+    #[test]
+    fn soundex_test() {
+        assert_eq!(soundex("Robert"), "R163");
+        assert_eq!(soundex("Rupert"), "R163");
+        assert_eq!(soundex("Ashcraft"), "A261");
+        assert_eq!(soundex("Tymczak"), "T522");
+        assert_eq!(soundex("Pfister"), "P236");
+        assert_eq!(soundex("a"), "A000");
+        assert_eq!(soundex("42"), "");
+    }
+
+    #[test]
+    fn names() {
+        for &algorithm in &[Algorithm::Metaphone, Algorithm::Soundex] {
+            assert_eq!(Algorithm::from_name(algorithm.name()), Some(algorithm));
+        }
+        assert_eq!(Algorithm::from_name("nysiis"), None);
+    }
+
+    #[test]
+    fn index_test() {
+        let table = form_table("the phone rang at night and one knight answered".as_bytes());
+        let index = PhoneticIndex::new(Algorithm::Metaphone, &table);
+        assert_eq!(index.matches("fone"), ["phone"]);
+        assert_eq!(index.matches("nite"), ["knight", "night"]);
+        assert!(index.matches("zzz").is_empty());
+
+        let index = PhoneticIndex::new(Algorithm::Soundex, &table);
+        assert_eq!(index.algorithm(), Algorithm::Soundex);
+        assert!(index.matches("fone").is_empty());
+    }
+}
@@ -0,0 +1,390 @@
+#![doc="
+    HTTP service, so editors and other programs can check text without paying for training on
+    every call. The model is loaded once and shared by a fixed pool of threads, each answering one
+    connection at a time.
+
+    ```text
+    POST /check HTTP/1.1
+    Content-Length: 43
+
+    {\"text\": \"Teh quick fox\", \"suggestions\": 3}
+    ```
+
+    answers with the misspelled words, their byte offsets into the text and their ranked
+    suggestions (`replacement` carries the misspelling's capitalization):
+
+    ```text
+    {\"misspellings\": [{\"offset\": 0, \"length\": 3, \"word\": \"Teh\", \"suggestions\": [
+        {\"word\": \"the\", \"distance\": 1, \"count\": 2, \"probability\": 0.1, \"score\": 0.1,
+         \"replacement\": \"The\"}]}]}
+    ```
+
+    Assumptions:
+        - One request per connection, the connection is closed after the response
+        - The body is JSON with a `text` string and optionally a `suggestions` count, and is
+          sized by Content-Length (no chunked bodies)
+        - Clients that don't send the whole request within REQUEST_TIMEOUT, or send overlong
+          lines, are cut off, so they can't hold on to a thread
+"]
+
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::mpsc::{sync_channel, Receiver};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use corrector::Corrector;
+use json::Json;
+use suggest::{candidate_json, DEFAULT_SUGGESTIONS};
+use text::{misspellings, Misspelling};
+
+pub const DEFAULT_ADDRESS: &str = "127.0.0.1:8080";
+/// Larger bodies are refused with 413
+pub const MAX_BODY: usize = 1 << 20;
+/// Longer request or header lines are refused with 431
+pub const MAX_LINE: usize = 8 << 10;
+/// More headers than this are refused with 431
+pub const MAX_HEADERS: usize = 100;
+/// Connections answered at once; more wait to be accepted
+pub const MAX_CONNECTIONS: usize = 64;
+/// How long the client has to send the whole request, and to take the whole response
+pub const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, PartialEq)]
+pub struct Request {
+    pub method: String,
+    pub path: String,
+    pub body: String,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Response {
+    pub status: u16,
+    pub body: Json,
+}
+
+impl Response {
+    fn error(status: u16, message: &str) -> Response {
+        Response { status, body: Json::object(vec![("error", Json::string(message))]) }
+    }
+
+    fn reason(&self) -> &'static str {
+        match self.status {
+            200 => "OK",
+            400 => "Bad Request",
+            404 => "Not Found",
+            405 => "Method Not Allowed",
+            413 => "Payload Too Large",
+            431 => "Request Header Fields Too Large",
+            _ => "Internal Server Error",
+        }
+    }
+
+    pub fn write_to<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        let body = self.body.to_string();
+        write!(writer, "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\
+                        Connection: close\r\n\r\n{}",
+               self.status, self.reason(), body.len(), body)?;
+        writer.flush()
+    }
+}
+
+/// Reads the request line, the headers and a Content-Length sized body.
+/// @return Err - the response to send instead, for requests that can't be read
+pub fn read_request<R: Read>(reader: R) -> Result<Request, Response> {
+    let mut reader = BufReader::new(reader);
+    let bad = |what: &str| Response::error(400, what);
+
+    let mut line = String::new();
+    read_line(&mut reader, &mut line).map_err(|e| e.unwrap_or_else(|| bad("could not read request")))?;
+    let mut parts = line.split_whitespace();
+    let (method, path) = match (parts.next(), parts.next(), parts.next()) {
+        (Some(method), Some(path), Some(version)) if version.starts_with("HTTP/") => (method.to_owned(), path.to_owned()),
+        _ => return Err(bad("malformed request line")),
+    };
+
+    let mut length = 0;
+    for headers in 0.. {
+        if headers == MAX_HEADERS {
+            return Err(Response::error(431, "too many headers"));
+        }
+        line.clear();
+        read_line(&mut reader, &mut line).map_err(|e| e.unwrap_or_else(|| bad("could not read headers")))?;
+        let header = line.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some(colon) = header.find(':') {
+            if header[..colon].eq_ignore_ascii_case("content-length") {
+                length = header[colon + 1..].trim().parse().map_err(|_| bad("bad Content-Length"))?;
+            }
+        }
+    }
+    if length > MAX_BODY {
+        return Err(Response::error(413, "body is too large"));
+    }
+
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body).map_err(|_| bad("body is shorter than Content-Length"))?;
+    let body = String::from_utf8(body).map_err(|_| bad("body is not UTF-8"))?;
+    Ok(Request { method, path, body })
+}
+
+/// Reads a line of at most MAX_LINE bytes
+/// @return Err - Some(response) for an overlong line, None if reading failed
+fn read_line<R: BufRead>(reader: &mut R, line: &mut String) -> Result<(), Option<Response>> {
+    reader.take(MAX_LINE as u64 + 1).read_line(line).map_err(|_| None)?;
+    if line.len() > MAX_LINE {
+        return Err(Some(Response::error(431, "line is too long")));
+    }
+    Ok(())
+}
+
+/// Routes a request. Only `POST /check` exists.
+pub fn respond(request: &Request, corrector: &Corrector) -> Response {
+    if request.path != "/check" {
+        return Response::error(404, "not found, use POST /check");
+    }
+    if request.method != "POST" {
+        return Response::error(405, "use POST");
+    }
+
+    let body = match Json::parse(&request.body) {
+        Ok(body) => body,
+        Err(e) => return Response::error(400, &format!("body is not JSON: {}", e)),
+    };
+    let text = match body.get("text").and_then(|t| t.as_str()) {
+        Some(text) => text,
+        None => return Response::error(400, "body needs a \"text\" string"),
+    };
+    let n = match body.get("suggestions") {
+        None => DEFAULT_SUGGESTIONS,
+        Some(n) => match n.as_f64() {
+            Some(n) if n >= 0.0 => n as usize,
+            _ => return Response::error(400, "\"suggestions\" must be a non-negative number"),
+        },
+    };
+
+    let found = misspellings(text, corrector).iter().map(|m| misspelling_json(m, corrector, n)).collect();
+    Response { status: 200, body: Json::object(vec![("misspellings", Json::Array(found))]) }
+}
+
+fn misspelling_json(misspelling: &Misspelling, corrector: &Corrector, n: usize) -> Json {
+    let suggestions = misspelling.suggestions.iter().take(n).map(|candidate| {
+        let mut json = candidate_json(candidate, corrector);
+        if let Json::Object(ref mut fields) = json {
+            fields.push(("replacement".to_owned(), Json::String(misspelling.replacement(candidate))));
+        }
+        json
+    });
+
+    Json::object(vec![
+        ("offset", Json::Number(misspelling.offset as f64)),
+        ("length", Json::Number(misspelling.word.len() as f64)),
+        ("word", Json::string(&misspelling.word)),
+        ("suggestions", Json::Array(suggestions.collect())),
+    ])
+}
+
+/// Answers one request on the connection, then closes it
+pub fn handle_client(stream: TcpStream, corrector: &Corrector) -> io::Result<()> {
+    answer_within(stream, corrector, REQUEST_TIMEOUT)
+}
+
+fn answer_within(mut stream: TcpStream, corrector: &Corrector, timeout: Duration) -> io::Result<()> {
+    stream.set_write_timeout(Some(timeout))?;
+    let reader = Deadline { stream: &stream, deadline: Instant::now() + timeout };
+    let response = match read_request(reader) {
+        Ok(request) => respond(&request, corrector),
+        Err(response) => response,
+    };
+    response.write_to(&mut stream)
+}
+
+/// Reads from a stream until a deadline. A socket's read timeout only bounds each read, so a
+/// client sending a byte at a time could otherwise take as long as it liked.
+struct Deadline<'a> {
+    stream: &'a TcpStream,
+    deadline: Instant,
+}
+
+impl<'a> Read for Deadline<'a> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let left = self.deadline.saturating_duration_since(Instant::now());
+        if left == Duration::from_secs(0) {
+            return Err(io::Error::new(io::ErrorKind::TimedOut, "request took too long"));
+        }
+        self.stream.set_read_timeout(Some(left))?;
+        self.stream.read(buf)
+    }
+}
+
+/// Accepts connections forever, handing each to one of MAX_CONNECTIONS threads. Accepting waits
+/// while they are all busy and the queue is full.
+pub fn serve(address: &str, corrector: Corrector) -> io::Result<()> {
+    let listener = TcpListener::bind(address)?;
+    let corrector = Arc::new(corrector);
+    let (sender, receiver) = sync_channel::<TcpStream>(MAX_CONNECTIONS);
+    let receiver = Arc::new(Mutex::new(receiver));
+
+    for _ in 0..MAX_CONNECTIONS {
+        let corrector = corrector.clone();
+        let receiver = receiver.clone();
+        thread::spawn(move || worker(&receiver, &corrector));
+    }
+
+    for stream in listener.incoming().flatten() {
+        if sender.send(stream).is_err() {
+            break;
+        }
+    }
+    Ok(())
+}
+
+/// Answers connections from the queue until it is closed
+fn worker(connections: &Mutex<Receiver<TcpStream>>, corrector: &Corrector) {
+    loop {
+        let stream = match connections.lock() {
+            Ok(receiver) => match receiver.recv() {
+                Ok(stream) => stream,
+                Err(_) => return,
+            },
+            Err(_) => return,
+        };
+        let _ = handle_client(stream, corrector);
+    }
+}
+
+#[cfg(test)]
+mod serve_tests {
+    use super::{answer_within, handle_client, read_request, respond, Request, Response, MAX_BODY, MAX_HEADERS, MAX_LINE};
+    use corrector::Corrector;
+    use json::Json;
+    use std::io::{Read, Write};
+    use std::net::{TcpListener, TcpStream};
+    use std::thread;
+    use std::time::{Duration, Instant};
+    use table::form_table;
+
+    fn corrector() -> Corrector {
+        Corrector::new(form_table("the quick brown fox the lazy dog".as_bytes()))
+    }
+
+    fn check(body: &str) -> Request {
+        Request { method: "POST".to_owned(), path: "/check".to_owned(), body: body.to_owned() }
+    }
+
+    #[test]
+    fn read_request_test() {
+        let raw = "POST /check HTTP/1.1\r\nHost: x\r\ncontent-length: 15\r\n\r\n{\"text\":\"teh\"}\n";
+        assert_eq!(read_request(raw.as_bytes()), Ok(check("{\"text\":\"teh\"}\n")));
+    }
+
+    #[test]
+    fn read_request_errors() {
+        assert_eq!(read_request("garbage\r\n\r\n".as_bytes()).unwrap_err().status, 400);
+        assert_eq!(read_request("POST /check HTTP/1.1\r\nContent-Length: 10\r\n\r\nshort".as_bytes()).unwrap_err().status,
+                   400);
+        assert_eq!(read_request("POST /check HTTP/1.1\r\nContent-Length: 99999999\r\n\r\n".as_bytes()).unwrap_err().status,
+                   413);
+    }
+
+    #[test]
+    fn read_request_limits() {
+        let long_line = format!("POST /{} HTTP/1.1\r\n\r\n", "a".repeat(MAX_LINE));
+        assert_eq!(read_request(long_line.as_bytes()).unwrap_err().status, 431);
+        let long_header = format!("POST /check HTTP/1.1\r\nX: {}\r\n\r\n", "a".repeat(MAX_LINE));
+        assert_eq!(read_request(long_header.as_bytes()).unwrap_err().status, 431);
+        let many_headers = format!("POST /check HTTP/1.1\r\n{}\r\n", "X: y\r\n".repeat(MAX_HEADERS));
+        assert_eq!(read_request(many_headers.as_bytes()).unwrap_err().status, 431);
+        let enough_headers = format!("POST /check HTTP/1.1\r\n{}\r\n", "X: y\r\n".repeat(MAX_HEADERS - 1));
+        assert!(read_request(enough_headers.as_bytes()).is_ok());
+    }
+
+    #[test]
+    fn respond_to_deep_nesting() {
+        let body = "[".repeat(MAX_BODY);
+        assert_eq!(respond(&check(&body), &corrector()).status, 400);
+    }
+
+    #[test]
+    fn respond_check() {
+        let response = respond(&check(r#"{"text": "Teh quikc fox", "suggestions": 1}"#), &corrector());
+        assert_eq!(response.status, 200);
+        let found = response.body.get("misspellings").and_then(|m| m.as_array()).unwrap();
+        assert_eq!(found.len(), 2);
+        assert_eq!(found[1].get("offset"), Some(&Json::Number(4.0)));
+        assert_eq!(found[1].get("length"), Some(&Json::Number(5.0)));
+        let suggestions = found[0].get("suggestions").and_then(|s| s.as_array()).unwrap();
+        assert_eq!(suggestions.len(), 1);
+        assert_eq!(suggestions[0].get("word"), Some(&Json::string("the")));
+        assert_eq!(suggestions[0].get("replacement"), Some(&Json::string("The")));
+    }
+
+    #[test]
+    fn respond_errors() {
+        let corrector = corrector();
+        assert_eq!(respond(&check("{\"txt\": 1}"), &corrector).status, 400);
+        assert_eq!(respond(&check("not json"), &corrector).status, 400);
+        assert_eq!(respond(&check("{\"text\": \"a\", \"suggestions\": -1}"), &corrector).status, 400);
+        let get = Request { method: "GET".to_owned(), path: "/check".to_owned(), body: String::new() };
+        assert_eq!(respond(&get, &corrector).status, 405);
+        let other = Request { method: "POST".to_owned(), path: "/".to_owned(), body: String::new() };
+        assert_eq!(respond(&other, &corrector).status, 404);
+    }
+
+    #[test]
+    fn response_format() {
+        let mut out = Vec::new();
+        Response { status: 200, body: Json::Array(vec![]) }.write_to(&mut out).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(),
+                   "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: 2\r\nConnection: close\r\n\r\n[]");
+    }
+
+    #[test]
+    fn over_tcp() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        let server = thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            handle_client(stream, &corrector()).unwrap();
+        });
+
+        let body = r#"{"text": "the lazy dgo"}"#;
+        let mut client = TcpStream::connect(address).unwrap();
+        write!(client, "POST /check HTTP/1.1\r\nContent-Length: {}\r\n\r\n{}", body.len(), body).unwrap();
+        let mut response = String::new();
+        client.read_to_string(&mut response).unwrap();
+        server.join().unwrap();
+
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(response.contains(r#"{"misspellings":[{"offset":9,"length":3,"word":"dgo","suggestions":[{"word":"dog""#));
+    }
+
+    #[test]
+    fn slow_client_is_cut_off() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        let server = thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let start = Instant::now();
+            answer_within(stream, &corrector(), Duration::from_millis(500)).unwrap();
+            start.elapsed()
+        });
+
+        // A byte every 100 ms keeps each read well within the timeout, so only a deadline for
+        // the whole request stops it at 500 ms rather than 500 ms after the last byte
+        let mut client = TcpStream::connect(address).unwrap();
+        for byte in b"POST " {
+            client.write_all(&[*byte]).unwrap();
+            thread::sleep(Duration::from_millis(100));
+        }
+        let mut response = String::new();
+        client.read_to_string(&mut response).unwrap();
+        let elapsed = server.join().unwrap();
+
+        assert!(response.starts_with("HTTP/1.1 400 Bad Request\r\n"), "{}", response);
+        assert!(elapsed < Duration::from_millis(800), "{:?}", elapsed);
+    }
+}
@@ -0,0 +1,453 @@
+#![doc="
+    Which parts of a document are prose worth spell checking. Plain text is checked whole;
+    Markdown without its code blocks, inline code and link targets; source code only inside its
+    comments and string literals.
+
+    Identifiers in code can be checked too: they are split into words at underscores, digits
+    and case changes (parseHttpResponse, MAX_LINE_LEN, HTTPServer), and each word of three or
+    more letters that isn't a keyword is checked like prose.
+
+    Assumptions:
+        - C-like languages use // and /* */ comments and \"...\" strings with backslash escapes,
+          plus Rust's raw strings (r\"...\", r#\"...\"#)
+        - Scripting languages (Python, shell, Ruby, YAML, TOML) use # comments and '...' or
+          \"...\" strings
+        - Markdown code fences are lines starting with ``` or ~~~
+"]
+
+use std::collections::HashMap;
+
+use corrector::Corrector;
+use text::{self, normalize, Misspelling};
+
+/// Identifier words shorter than this (i, id, fd) are taken to be abbreviations
+pub const MIN_IDENTIFIER_WORD_CHARS: usize = 3;
+
+/// Keywords, built-in type names and the usual abbreviations of the supported languages, which
+/// aren't English words
+static KEYWORDS: &[&str] = &[
+    // Rust
+    "async", "await", "const", "crate", "dyn", "enum", "extern", "impl", "isize", "loop", "match", "mod",
+    "move", "mut", "pub", "self", "str", "struct", "super", "trait", "type", "unsafe", "usize", "where",
+    "vec", "println", "eprintln", "writeln", "ref", "cfg", "repr", "iter", "unwrap", "rustc",
+    "btree", "hasher", "mpsc", "refcell", "dbg", "todo", "alloc", "dealloc", "deque", "stdin", "stdout",
+    "stderr", "eof", "cmp", "ord", "toml", "serde", "tokio",
+    // Abbreviations in identifiers of any language
+    "src", "dst", "buf", "idx", "ptr", "ctx", "msg", "tmp", "num", "val", "cnt", "opt",
+    "arg", "param", "params", "req", "resp", "res", "addr", "env", "dir", "cwd", "rng", "lhs", "rhs",
+    "utf", "ascii", "http", "https", "tcp", "udp", "uri", "url", "json", "xml", "html", "regex", "sqrt",
+    "abs", "min", "max", "prev", "cur", "pos", "col", "elem", "impls", "util", "utils", "sync",
+    // C, C++, Java, C#, Go, JavaScript, TypeScript, Python
+    "auto", "bool", "char", "elif", "goto", "int", "sizeof", "typedef", "unsigned", "void",
+    "volatile", "nullptr", "typename", "namespace", "func", "var", "nil", "typeof", "instanceof",
+    "printf", "malloc", "calloc", "realloc", "def", "init", "args", "kwargs", "len", "std", "fmt",
+];
+
+/// How a document's checkable regions are found
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Syntax {
+    Plain,
+    Markdown,
+    CLike,
+    Hash,
+}
+
+impl Syntax {
+    /// From a file extension. Unknown extensions are treated as plain text.
+    pub fn from_extension(extension: &str) -> Syntax {
+        match extension {
+            "md" | "markdown" => Syntax::Markdown,
+            "rs" | "c" | "h" | "cc" | "cpp" | "hpp" | "cs" | "go" | "java" | "js" | "jsx" | "kt" | "scala"
+                | "swift" | "ts" | "tsx" => Syntax::CLike,
+            "py" | "sh" | "bash" | "rb" | "pl" | "r" | "yaml" | "yml" | "toml" => Syntax::Hash,
+            _ => Syntax::Plain,
+        }
+    }
+
+    /// From an LSP language identifier. Unknown languages are treated as plain text.
+    pub fn from_language_id(id: &str) -> Syntax {
+        match id {
+            "markdown" => Syntax::Markdown,
+            "c" | "cpp" | "csharp" | "go" | "java" | "javascript" | "javascriptreact" | "kotlin" | "rust"
+                | "scala" | "swift" | "typescript" | "typescriptreact" => Syntax::CLike,
+            "python" | "shellscript" | "ruby" | "perl" | "r" | "yaml" | "toml" | "makefile" => Syntax::Hash,
+            _ => Syntax::Plain,
+        }
+    }
+}
+
+/// Byte ranges of the text to check, in order and non-overlapping
+pub fn regions(text: &str, syntax: Syntax) -> Vec<(usize, usize)> {
+    match syntax {
+        Syntax::Plain => vec![(0, text.len())],
+        Syntax::Markdown => markdown_regions(text),
+        Syntax::CLike => code_regions(text, b"//", true),
+        Syntax::Hash => code_regions(text, b"#", false),
+    }
+}
+
+/// Misspellings inside the checked regions, with offsets into the whole text
+pub fn misspellings(text: &str, syntax: Syntax, corrector: &Corrector) -> Vec<Misspelling> {
+    regions(text, syntax).into_iter()
+        .flat_map(|(start, end)| {
+            text::misspellings(&text[start..end], corrector).into_iter()
+                .map(move |m| Misspelling { offset: m.offset + start, ..m })
+        })
+        .collect()
+}
+
+/// Misspellings in the checked regions and in the words of identifiers, in document order
+pub fn code_misspellings(text: &str, syntax: Syntax, corrector: &Corrector) -> Vec<Misspelling> {
+    let mut found = misspellings(text, syntax, corrector);
+    // The same identifiers come back again and again
+    let mut checked: HashMap<&str, Option<Vec<_>>> = HashMap::new();
+
+    for (offset, identifier) in identifiers(text, syntax) {
+        for (start, word) in identifier_words(identifier) {
+            if word.chars().count() < MIN_IDENTIFIER_WORD_CHARS || KEYWORDS.contains(&normalize(word).as_str()) {
+                continue;
+            }
+            let suggestions = checked.entry(word).or_insert_with(|| {
+                if corrector.is_accepted(word) { None } else { Some(corrector.suggestions(word)) }
+            });
+            if let Some(ref suggestions) = *suggestions {
+                found.push(Misspelling { offset: offset + start, word: word.to_owned(), suggestions: suggestions.clone() });
+            }
+        }
+    }
+    found.sort_by_key(|m| m.offset);
+    found
+}
+
+/// The identifiers of the code outside the checked regions, with their offsets. Empty for
+/// plain text and Markdown.
+pub fn identifiers(text: &str, syntax: Syntax) -> Vec<(usize, &str)> {
+    if syntax == Syntax::Plain || syntax == Syntax::Markdown {
+        return Vec::new();
+    }
+    let bytes = text.as_bytes();
+    let mut found = Vec::new();
+    for (start, end) in complement(&regions(text, syntax), text.len()) {
+        let mut i = start;
+        while i < end {
+            let token_end = (i..end).find(|&j| !is_identifier_byte(bytes[j])).unwrap_or(end);
+            if token_end == i {
+                i += 1;
+                continue;
+            }
+            // Numbers (1e10, 0xff, 10u32) aren't identifiers
+            if !bytes[i].is_ascii_digit() {
+                found.push((i, &text[i..token_end]));
+            }
+            i = token_end;
+        }
+    }
+    found
+}
+
+/// The words of an identifier, split at underscores, digits and case changes, with their
+/// offsets: parseHTTPResponse2 is parse, HTTP and Response
+pub fn identifier_words(identifier: &str) -> Vec<(usize, &str)> {
+    let bytes = identifier.as_bytes();
+    let mut words = Vec::new();
+    let mut start = None;
+
+    for i in 0..=bytes.len() {
+        let boundary = match (i.checked_sub(1).map(|p| bytes[p]), bytes.get(i)) {
+            (_, None) => true,
+            (_, Some(b)) if !b.is_ascii_alphabetic() => true,
+            (Some(p), Some(b)) if p.is_ascii_lowercase() && b.is_ascii_uppercase() => true,
+            // The last capital of an acronym starts the next word: HTTPServer
+            (Some(p), Some(b)) => p.is_ascii_uppercase() && b.is_ascii_uppercase()
+                && bytes.get(i + 1).is_some_and(|n| n.is_ascii_lowercase()),
+            _ => false,
+        };
+        if boundary {
+            if let Some(s) = start.take() {
+                words.push((s, &identifier[s..i]));
+            }
+        }
+        if bytes.get(i).is_some_and(|b| b.is_ascii_alphabetic()) && start.is_none() {
+            start = Some(i);
+        }
+    }
+    words
+}
+
+fn is_identifier_byte(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || b == b'_'
+}
+
+/// Comments and string literals. `block` enables /* */ comments and Rust raw strings; without
+/// it ' also quotes strings.
+fn code_regions(text: &str, line_comment: &[u8], block: bool) -> Vec<(usize, usize)> {
+    let bytes = text.as_bytes();
+    let mut regions = Vec::new();
+    let mut i = 0;
+
+    while i < bytes.len() {
+        if bytes[i..].starts_with(line_comment) {
+            let start = i + line_comment.len();
+            let end = find(bytes, start, b"\n").unwrap_or(bytes.len());
+            regions.push((start, end));
+            i = end;
+        } else if block && bytes[i..].starts_with(b"/*") {
+            let end = find(bytes, i + 2, b"*/");
+            regions.push((i + 2, end.unwrap_or(bytes.len())));
+            i = end.map_or(bytes.len(), |end| end + 2);
+        } else if let Some(hashes) = raw_string_hashes(bytes, i).filter(|_| block) {
+            let open = i + 1 + hashes;
+            let mut close = vec![b'"'];
+            close.extend(vec![b'#'; hashes]);
+            let end = find(bytes, open + 1, &close);
+            regions.push((open + 1, end.unwrap_or(bytes.len())));
+            i = end.map_or(bytes.len(), |end| end + close.len());
+        } else if bytes[i] == b'"' || (!block && bytes[i] == b'\'') {
+            let end = string_end(bytes, i + 1, bytes[i]);
+            regions.push((i + 1, end));
+            i = end + 1;
+        } else if block && bytes[i] == b'\'' {
+            i = char_literal_end(bytes, i);
+        } else {
+            i += 1;
+        }
+    }
+    regions
+}
+
+/// The number of # of a Rust raw string (r\"...\", br#\"...\"#) starting at `i`, if one does
+fn raw_string_hashes(bytes: &[u8], i: usize) -> Option<usize> {
+    if bytes[i] != b'r' {
+        return None;
+    }
+    let mut before = i.checked_sub(1).map(|p| bytes[p]);
+    if before == Some(b'b') {
+        before = i.checked_sub(2).map(|p| bytes[p]);
+    }
+    if before.is_some_and(is_identifier_byte) {
+        return None;
+    }
+    let hashes = bytes[i + 1..].iter().take_while(|&&b| b == b'#').count();
+    if bytes.get(i + 1 + hashes) == Some(&b'"') { Some(hashes) } else { None }
+}
+
+fn find(bytes: &[u8], from: usize, needle: &[u8]) -> Option<usize> {
+    (from..bytes.len()).find(|&i| bytes[i..].starts_with(needle))
+}
+
+/// Index of the closing quote (or the end of the text), skipping backslash escapes
+fn string_end(bytes: &[u8], mut i: usize, quote: u8) -> usize {
+    while i < bytes.len() && bytes[i] != quote {
+        i += if bytes[i] == b'\\' { 2 } else { 1 };
+    }
+    i.min(bytes.len())
+}
+
+/// Skips a character literal ('a', '\n'), or just the quote if it isn't one (a Rust lifetime)
+fn char_literal_end(bytes: &[u8], quote: usize) -> usize {
+    let close = if bytes.get(quote + 1) == Some(&b'\\') {
+        (quote + 2..bytes.len().min(quote + 12)).find(|&i| bytes[i] == b'\'')
+    } else {
+        let width = bytes.get(quote + 1).map_or(1, |&b| utf8_width(b));
+        if bytes.get(quote + 1 + width) == Some(&b'\'') { Some(quote + 1 + width) } else { None }
+    };
+    close.map_or(quote + 1, |close| close + 1)
+}
+
+fn utf8_width(first: u8) -> usize {
+    match first {
+        0xf0..=0xff => 4,
+        0xe0..=0xef => 3,
+        0xc0..=0xdf => 2,
+        _ => 1,
+    }
+}
+
+/// Everything outside fenced code blocks, `inline code` and link targets
+fn markdown_regions(text: &str) -> Vec<(usize, usize)> {
+    let mut excluded = Vec::new();
+    let mut fence: Option<(usize, &str)> = None;
+    let mut offset = 0;
+
+    for line in text.split_inclusive('\n') {
+        let trimmed = line.trim_start();
+        let marker = if trimmed.starts_with("```") { Some("```") } else if trimmed.starts_with("~~~") { Some("~~~") } else { None };
+        match (fence, marker) {
+            (None, Some(marker)) => fence = Some((offset, marker)),
+            (Some((start, open)), Some(marker)) if marker == open => {
+                excluded.push((start, offset + line.len()));
+                fence = None;
+            }
+            (None, None) => excluded.extend(inline_code_and_links(line, offset)),
+            _ => (),
+        }
+        offset += line.len();
+    }
+    if let Some((start, _)) = fence {
+        excluded.push((start, text.len()));
+    }
+    complement(&excluded, text.len())
+}
+
+fn inline_code_and_links(line: &str, offset: usize) -> Vec<(usize, usize)> {
+    let bytes = line.as_bytes();
+    let mut spans = Vec::new();
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let close = match bytes[i] {
+            b'`' => find(bytes, i + 1, b"`"),
+            b']' if bytes.get(i + 1) == Some(&b'(') => find(bytes, i + 2, b")"),
+            _ => None,
+        };
+        match close {
+            Some(close) => {
+                spans.push((offset + i, offset + close + 1));
+                i = close + 1;
+            }
+            None => i += 1,
+        }
+    }
+    spans
+}
+
+/// The ranges of 0..len not covered by the sorted, non-overlapping `excluded`
+fn complement(excluded: &[(usize, usize)], len: usize) -> Vec<(usize, usize)> {
+    let mut regions = Vec::new();
+    let mut start = 0;
+    for &(from, to) in excluded {
+        if from > start {
+            regions.push((start, from));
+        }
+        start = to;
+    }
+    if start < len {
+        regions.push((start, len));
+    }
+    regions
+}
+
+#[cfg(test)]
+mod source_tests {
+    use super::{code_misspellings, identifier_words, identifiers, misspellings, regions, Syntax};
+    use corrector::Corrector;
+    use table::form_table;
+
+    fn checked(text: &str, syntax: Syntax) -> Vec<&str> {
+        regions(text, syntax).into_iter().map(|(start, end)| &text[start..end]).collect()
+    }
+
+    #[test]
+    fn language_ids() {
+        assert_eq!(Syntax::from_language_id("rust"), Syntax::CLike);
+        assert_eq!(Syntax::from_language_id("python"), Syntax::Hash);
+        assert_eq!(Syntax::from_language_id("markdown"), Syntax::Markdown);
+        assert_eq!(Syntax::from_language_id("plaintext"), Syntax::Plain);
+        assert_eq!(Syntax::from_language_id("brainfuck"), Syntax::Plain);
+    }
+
+    #[test]
+    fn extensions() {
+        assert_eq!(Syntax::from_extension("rs"), Syntax::CLike);
+        assert_eq!(Syntax::from_extension("py"), Syntax::Hash);
+        assert_eq!(Syntax::from_extension("md"), Syntax::Markdown);
+        assert_eq!(Syntax::from_extension("txt"), Syntax::Plain);
+    }
+
+    #[test]
+    fn plain_is_whole_text() {
+        assert_eq!(checked("teh text", Syntax::Plain), vec!["teh text"]);
+    }
+
+    #[test]
+    fn c_like_comments_and_strings() {
+        let code = "fn main() { // say helo\n    let s = \"wrold \\\"x\\\"\"; /* blok */ let c = '\"'; }\n";
+        assert_eq!(checked(code, Syntax::CLike), vec![" say helo", "wrold \\\"x\\\"", " blok "]);
+    }
+
+    #[test]
+    fn c_like_lifetimes_are_not_quotes() {
+        let code = "fn f<'a>(x: &'a str) -> char { 'é' } // dcoument";
+        assert_eq!(checked(code, Syntax::CLike), vec![" dcoument"]);
+    }
+
+    #[test]
+    fn rust_raw_strings() {
+        let code = "let a = r\"C:\\tmep\"; let b = r#\"say \"helo\"\"#; let c = br\"bytse\"; for_r(\"x\");";
+        assert_eq!(checked(code, Syntax::CLike), vec!["C:\\tmep", "say \"helo\"", "bytse", "x"]);
+    }
+
+    #[test]
+    fn rust_doc_comments() {
+        let code = "//! Crate dcos\n/// Retruns the sum\nfn add() {}\n/** Blcok */";
+        assert_eq!(checked(code, Syntax::CLike), vec!["! Crate dcos", "/ Retruns the sum", "* Blcok "]);
+    }
+
+    #[test]
+    fn unterminated_regions_run_to_the_end() {
+        assert_eq!(checked("x = 1 /* opne", Syntax::CLike), vec![" opne"]);
+        assert_eq!(checked("x = \"opne", Syntax::CLike), vec!["opne"]);
+    }
+
+    #[test]
+    fn hash_comments_and_strings() {
+        let code = "x = 'sinlge' # cmoment\ny = \"dubble\"\n";
+        assert_eq!(checked(code, Syntax::Hash), vec!["sinlge", " cmoment", "dubble"]);
+    }
+
+    #[test]
+    fn markdown_skips_code() {
+        let doc = "Some txet with `inlin code` and [a lnik](docs/pgae.md).\n```rust\nlet wrod = 1;\n```\nEnd.\n";
+        assert_eq!(checked(doc, Syntax::Markdown),
+                   vec!["Some txet with ", " and [a lnik", ".\n", "End.\n"]);
+    }
+
+    #[test]
+    fn identifier_words_test() {
+        assert_eq!(identifier_words("snake_case_name"), vec![(0, "snake"), (6, "case"), (11, "name")]);
+        assert_eq!(identifier_words("camelCaseName"), vec![(0, "camel"), (5, "Case"), (9, "Name")]);
+        assert_eq!(identifier_words("HTTPServer2go"), vec![(0, "HTTP"), (4, "Server"), (11, "go")]);
+        assert_eq!(identifier_words("MAX_LINE_LEN"), vec![(0, "MAX"), (4, "LINE"), (9, "LEN")]);
+        assert_eq!(identifier_words("__init__"), vec![(2, "init")]);
+        assert!(identifier_words("_1").is_empty());
+    }
+
+    #[test]
+    fn identifiers_skip_comments_strings_and_numbers() {
+        let code = "let max_len = 0x1f + 1e10; // not_this\nprintln!(\"nor_this {}\", x.countWrods());";
+        let found: Vec<&str> = identifiers(code, Syntax::CLike).into_iter().map(|(_, i)| i).collect();
+        assert_eq!(found, vec!["let", "max_len", "println", "x", "countWrods"]);
+        assert!(identifiers(code, Syntax::Markdown).is_empty());
+    }
+
+    #[test]
+    fn code_misspellings_include_identifier_words() {
+        let corrector = Corrector::new(form_table("count the words in a line and receive a buffer".as_bytes()));
+        let code = "fn count_wrods(line: &str) -> usize { recieveBuffer(line) } // the wrods";
+        let misspellings = code_misspellings(code, Syntax::CLike, &corrector);
+        let found: Vec<(usize, &str, String)> = misspellings.iter()
+            .map(|m| (m.offset, m.word.as_str(), m.replacement(&m.suggestions[0])))
+            .collect();
+        assert_eq!(found, vec![
+            (9, "wrods", "words".to_owned()),
+            (38, "recieve", "receive".to_owned()),
+            (67, "wrods", "words".to_owned()),
+        ]);
+    }
+
+    #[test]
+    fn common_identifier_abbreviations() {
+        let corrector = Corrector::new(form_table("let the next test".as_bytes()));
+        let code = "let idx_ref = src_buf.iter().next().unwrap(); #[cfg(test)] let resp_msg = req_ctx;";
+        let found: Vec<String> = code_misspellings(code, Syntax::CLike, &corrector).into_iter().map(|m| m.word).collect();
+        assert!(found.is_empty(), "{:?}", found);
+    }
+
+    #[test]
+    fn misspellings_have_document_offsets() {
+        let corrector = Corrector::new(form_table("say hello world".as_bytes()));
+        let found = misspellings("let helo = 1; // say helo wrold", Syntax::CLike, &corrector);
+        let words: Vec<(usize, &str)> = found.iter().map(|m| (m.offset, m.word.as_str())).collect();
+        assert_eq!(words, vec![(21, "helo"), (26, "wrold")]);
+    }
+}
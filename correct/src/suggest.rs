@@ -0,0 +1,220 @@
+#![doc="
+    Ranked suggestion lists, for people and for programs. The interactive mode prints the top
+    suggestions for every typed word with their edit distance and corpus probability; the batch
+    mode prints one JSON object per input word with every candidate and its scores, so a
+    reviewer can see why a correction was picked.
+
+    In the interactive mode, `:add word...` adds words to the personal dictionary, or only for the
+    session when it has no file.
+"]
+
+use std::io::{self, BufRead, BufReader, Read, Write};
+
+use corrector::{Candidate, Corrector};
+use json::Json;
+
+pub const DEFAULT_SUGGESTIONS: usize = 5;
+
+pub fn candidate_json(candidate: &Candidate, corrector: &Corrector) -> Json {
+    Json::object(vec![
+        ("word", Json::string(&candidate.word)),
+        ("distance", Json::Number(candidate.distance as f64)),
+        ("count", Json::Number(candidate.count as f64)),
+        ("probability", Json::Number(corrector.phrase_probability(&candidate.word))),
+        ("score", Json::Number(candidate.score)),
+    ])
+}
+
+/// {"word", "known", "correction", "confidence", "candidates": [...]} for a single word. The
+/// correction has the word's capitalization and is null below the minimum confidence, the
+/// candidates are lowercase lookup keys.
+pub fn word_json(word: &str, corrector: &Corrector) -> Json {
+    let (candidates, best) = corrector.suggestions_with_confidence(word);
+    let (correction, confidence) = match best {
+        Some((best, confidence)) if confidence >= corrector.min_confidence() =>
+            (Json::string(&best), Json::Number(confidence)),
+        Some((_, confidence)) => (Json::Null, Json::Number(confidence)),
+        None => (Json::Null, Json::Null),
+    };
+
+    Json::object(vec![
+        ("word", Json::string(word)),
+        ("known", Json::Bool(corrector.is_accepted(word))),
+        ("correction", correction),
+        ("confidence", confidence),
+        ("candidates", Json::Array(candidates.iter().map(|c| candidate_json(c, corrector)).collect())),
+    ])
+}
+
+/// Reads one word per line and writes one JSON object per line
+pub fn batch<R: Read, W: Write>(reader: R, mut writer: W, corrector: &Corrector) -> io::Result<()> {
+    for line in BufReader::new(reader).lines() {
+        let line = line?;
+        let word = line.trim();
+        if word.is_empty() {
+            continue;
+        }
+        writeln!(writer, "{}", word_json(word, corrector))?;
+    }
+    writer.flush()
+}
+
+/// Human readable list of the top `n` suggestions for a word
+pub fn format_suggestions(word: &str, corrector: &Corrector, n: usize) -> String {
+    let candidates = corrector.suggestions(word);
+    let mut out = if corrector.is_accepted(word) {
+        format!("{} (known)\n", word)
+    } else if candidates.is_empty() {
+        format!("{} (unknown, no suggestions)\n", word)
+    } else {
+        format!("{} (unknown)\n", word)
+    };
+
+    let width = candidates.iter().take(n).map(|c| c.word.chars().count()).max().unwrap_or(0);
+    for (i, candidate) in candidates.iter().take(n).enumerate() {
+        out.push_str(&format!("  {:>2}. {:<width$}  distance {}  count {}  p {:.6}\n",
+                              i + 1, candidate.word, candidate.distance, candidate.count,
+                              corrector.phrase_probability(&candidate.word), width = width));
+    }
+    out
+}
+
+/// Prompts for words until end of input, printing suggestions for each
+pub fn repl<R: BufRead, W: Write>(input: R, mut output: W, corrector: &mut Corrector, n: usize) -> io::Result<()> {
+    let mut lines = input.lines();
+
+    loop {
+        write!(output, "> ")?;
+        output.flush()?;
+
+        let line = match lines.next() {
+            Some(line) => line?,
+            None => break,
+        };
+        if line.trim_start().starts_with(":add") {
+            for word in line.split_whitespace().skip(1) {
+                match corrector.personal_mut().add(word) {
+                    Ok(true) if corrector.personal().is_saved() =>
+                        writeln!(output, "added {} to the personal dictionary", word)?,
+                    Ok(true) => writeln!(output, "added {} for this session only, there is no dictionary file", word)?,
+                    Ok(false) => writeln!(output, "{} is already in the personal dictionary", word)?,
+                    Err(e) => writeln!(output, "could not add {}: {}", word, e)?,
+                }
+            }
+            continue;
+        }
+        for word in line.split_whitespace() {
+            write!(output, "{}", format_suggestions(word, corrector, n))?;
+        }
+    }
+    writeln!(output)
+}
+
+#[cfg(test)]
+mod suggest_tests {
+    use std::{env, fs};
+
+    use super::{batch, format_suggestions, repl, word_json};
+    use corrector::Corrector;
+    use json::Json;
+    use personal::PersonalDictionary;
+    use table::form_table;
+
+    fn corrector() -> Corrector {
+        Corrector::new(form_table("hello hello hell world".as_bytes()))
+    }
+
+    #[test]
+    fn word_json_test() {
+        let json = word_json("helo", &corrector());
+        assert_eq!(json.get("known"), Some(&Json::Bool(false)));
+        assert_eq!(json.get("correction"), Some(&Json::string("hello")));
+        assert_eq!(json.to_string(),
+                   "{\"word\":\"helo\",\"known\":false,\"correction\":\"hello\",\"confidence\":0.6666666666666666,\
+                    \"candidates\":[\
+                    {\"word\":\"hello\",\"distance\":1,\"count\":2,\"probability\":0.5,\"score\":0.5},\
+                    {\"word\":\"hell\",\"distance\":1,\"count\":1,\"probability\":0.25,\"score\":0.25}]}");
+    }
+
+    #[test]
+    fn word_json_ignores_case() {
+        let json = word_json("Hello", &corrector());
+        assert_eq!(json.get("known"), Some(&Json::Bool(true)));
+        let json = word_json("HELO", &corrector());
+        assert_eq!(json.get("known"), Some(&Json::Bool(false)));
+        assert_eq!(json.get("correction"), Some(&Json::string("HELLO")));
+        assert!(json.to_string().contains("\"candidates\":[{\"word\":\"hello\""));
+    }
+
+    #[test]
+    fn word_json_below_min_confidence() {
+        let mut corrector = corrector();
+        corrector.set_min_confidence(0.9);
+        let json = word_json("helo", &corrector);
+        assert_eq!(json.get("correction"), Some(&Json::Null));
+        assert_eq!(json.get("confidence").and_then(|c| c.as_f64()), Some(2.0 / 3.0));
+        assert_eq!(word_json("wrld", &corrector).get("correction"), Some(&Json::string("world")));
+    }
+
+    #[test]
+    fn word_json_no_candidates() {
+        let json = word_json("zzzzzzz", &corrector());
+        assert_eq!(json.get("correction"), Some(&Json::Null));
+        assert_eq!(json.get("confidence"), Some(&Json::Null));
+        assert_eq!(json.get("candidates"), Some(&Json::Array(vec![])));
+    }
+
+    #[test]
+    fn batch_writes_json_lines() {
+        let mut out = Vec::new();
+        batch("helo\n\nworld\n".as_bytes(), &mut out, &corrector()).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        let lines: Vec<&str> = out.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].starts_with("{\"word\":\"helo\""));
+        assert!(lines[1].starts_with("{\"word\":\"world\",\"known\":true,\"correction\":\"world\""));
+    }
+
+    #[test]
+    fn format_suggestions_test() {
+        assert_eq!(format_suggestions("helo", &corrector(), 1),
+                   "helo (unknown)\n   1. hello  distance 1  count 2  p 0.500000\n");
+        assert!(format_suggestions("hell", &corrector(), 5).starts_with("hell (known)\n   1. hell "));
+        assert_eq!(format_suggestions("zzzzzzz", &corrector(), 5), "zzzzzzz (unknown, no suggestions)\n");
+    }
+
+    #[test]
+    fn repl_test() {
+        let mut out = Vec::new();
+        repl("helo wrld\n".as_bytes(), &mut out, &mut corrector(), 2).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        assert!(out.starts_with("> helo (unknown)\n"));
+        assert!(out.contains("wrld (unknown)\n   1. world  distance 1"));
+        assert!(out.ends_with("> \n"));
+    }
+
+    #[test]
+    fn repl_adds_words() {
+        let mut out = Vec::new();
+        let mut corrector = corrector();
+        repl(":add helo\n:add helo\nhelo\n".as_bytes(), &mut out, &mut corrector, 2).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        assert!(out.contains("added helo for this session only, there is no dictionary file\n"));
+        assert!(out.contains("helo is already in the personal dictionary\n"));
+        assert!(out.contains("helo (known)\n   1. helo "));
+        assert!(corrector.is_accepted("helo"));
+    }
+
+    #[test]
+    fn repl_saves_words_with_a_dictionary() {
+        let path = env::temp_dir().join(format!("correct-repl-{}.txt", ::std::process::id()));
+        let _ = fs::remove_file(&path);
+        let mut out = Vec::new();
+        let mut corrector = corrector();
+        corrector.set_personal(PersonalDictionary::open(&path).unwrap());
+        repl(":add helo\n".as_bytes(), &mut out, &mut corrector, 2).unwrap();
+        assert!(String::from_utf8(out).unwrap().contains("added helo to the personal dictionary\n"));
+        assert_eq!(fs::read_to_string(&path).unwrap(), "helo\n");
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,292 @@
+#![doc="
+    Training phase. Consumes a corpus of correctly spelled text and counts the number of
+    occurrences of each (lowercased, punctuation stripped) word, and optionally of each pair of
+    consecutive words (bigrams) for context-aware correction.
+"]
+
+use std::collections::{BTreeMap, HashMap};
+use std::io::{BufRead, BufReader, Read};
+
+pub type CountTable = HashMap<String, usize>;
+pub type BigramTable = HashMap<(String, String), usize>;
+
+/// Characters rarer than this share of all characters in the vocabulary are left out of the
+/// alphabet, so a stray symbol in a large corpus doesn't blow up candidate generation
+const ALPHABET_MIN_SHARE: f64 = 0.00001;
+
+/// Characters that end a sentence, and with it the chain of bigrams
+const SENTENCE_ENDS: &[char] = &['.', '!', '?'];
+
+pub fn parse_input(input: String) -> String {
+    // These should get deleted from original string
+    let chars_to_trim = &["!", "?", "\"", "'"];
+    // These should simply be replaced by space, otherwise "hello,world" becomes "helloworld"
+    let chars_to_replace = &[",", "."];
+    let mut result = input.to_lowercase();
+    for character in chars_to_trim {
+        result = result.replace(character, "");
+    }
+    for character in chars_to_replace {
+        result = result.replace(character, " ");
+    }
+    result
+}
+
+
+pub fn form_table<R: Read>(reader: R) -> CountTable {
+    form_tables(reader).0
+}
+
+/// Counts words and bigrams in one pass. Bigrams run across line breaks, since sentences in
+/// a corpus often do, but not across the end of a sentence.
+pub fn form_tables<R: Read>(reader: R) -> (CountTable, BigramTable) {
+    let mut lines = BufReader::new(reader).lines();
+    let mut table = CountTable::new();
+    let mut bigrams = BigramTable::new();
+    let mut previous: Option<String> = None;
+
+    while let Some(Ok(line)) = lines.next() {
+        for (i, sentence) in line.split(SENTENCE_ENDS).enumerate() {
+            if i > 0 {
+                previous = None;
+            }
+            let replaced_line = parse_input(sentence.to_owned());
+            let words: Vec<&str> = replaced_line.split_whitespace().collect();
+            for word in words {
+                increment_word(&mut table, word.to_owned());
+                if let Some(previous) = previous {
+                    increment_bigram(&mut bigrams, previous, word.to_owned());
+                }
+                previous = Some(word.to_owned());
+            }
+        }
+    }
+    (table, bigrams)
+}
+
+
+/// The characters words in the table are made of, in sorted order. Any known word can only
+/// contain these, so they are the only ones worth inserting or substituting.
+pub fn alphabet_of(table: &CountTable) -> Vec<char> {
+    let mut seen: BTreeMap<char, usize> = BTreeMap::new();
+    for (word, count) in table {
+        for c in word.chars() {
+            *seen.entry(c).or_insert(0) += *count;
+        }
+    }
+
+    let total: usize = seen.values().sum();
+    seen.into_iter()
+        .filter(|&(_, count)| count as f64 >= total as f64 * ALPHABET_MIN_SHARE)
+        .map(|(c, _)| c)
+        .collect()
+}
+
+
+pub fn increment_word(map: &mut CountTable, word: String) {
+    *map.entry(word).or_insert(0) += 1;
+}
+
+pub fn increment_bigram(map: &mut BigramTable, first: String, second: String) {
+    *map.entry((first, second)).or_insert(0) += 1;
+}
+
+#[cfg(test)]
+mod form_table_tests {
+    use super::{CountTable, form_table};
+    use std::io::{Read, Result};
+
+    #[test]
+    fn form_table_test_basic_1() {
+        let table = make_test_table("Hello, world!");
+        assert_saved(&table, "world", 1);
+        assert_saved(&table, "hello", 1);
+        assert_none(&table, "hellooo");
+    }
+
+    #[test]
+    fn form_table_test_basic_2() {
+        let table = make_test_table("Of the dogs, By the dogs, For the dogs");
+        assert_saved(&table, "dogs", 3);
+        assert_saved(&table, "the", 3);
+        assert_saved(&table, "of", 1);
+        assert_none(&table, "people");
+    }
+
+    #[test]
+    fn form_table_test_empty() {
+        let table = make_test_table("");
+        assert_none(&table, "Hi");
+    }
+
+    fn make_test_table(input: &str) -> CountTable {
+        let mock_read = StringReader::new(input.to_owned());
+        form_table(mock_read)
+    }
+
+    fn assert_saved(table: &CountTable, test: &str, expected_num: usize) {
+        assert_eq!(Some(&expected_num), table.get(test));
+    }
+
+    fn assert_none(table: &CountTable, test: &str) {
+        assert_eq!(None, table.get(test));
+    }
+
+    struct StringReader {
+        contents: Vec<u8>,
+        position: usize,
+    }
+
+    impl StringReader {
+        fn new(s: String) -> Self {
+            StringReader {
+                contents: s.into_bytes(),
+                position: 0,
+            }
+        }
+    }
+
+    impl Read for StringReader {
+        fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+            let mut count = 0;
+
+            while self.position < self.contents.len() && count < buf.len() {
+                buf[count] = self.contents[self.position];
+                count += 1;
+                self.position += 1;
+            }
+
+            Ok(count)
+        }
+    }
+}
+
+#[cfg(test)]
+mod form_tables_tests {
+    use super::form_tables;
+
+    #[test]
+    fn counts_bigrams() {
+        let (table, bigrams) = form_tables("Of the dogs, By the dogs,\nFor the dogs".as_bytes());
+        assert_eq!(Some(&3), table.get("dogs"));
+        assert_eq!(Some(&3), bigrams.get(&("the".to_owned(), "dogs".to_owned())));
+        assert_eq!(Some(&1), bigrams.get(&("dogs".to_owned(), "for".to_owned())));
+        assert_eq!(None, bigrams.get(&("dogs".to_owned(), "the".to_owned())));
+    }
+
+    #[test]
+    fn no_bigrams_across_sentences() {
+        let (table, bigrams) = form_tables("The end. Then more!\nAnd why? Because".as_bytes());
+        assert_eq!(Some(&1), table.get("end"));
+        assert_eq!(Some(&1), bigrams.get(&("the".to_owned(), "end".to_owned())));
+        assert_eq!(None, bigrams.get(&("end".to_owned(), "then".to_owned())));
+        assert_eq!(None, bigrams.get(&("more".to_owned(), "and".to_owned())));
+        assert_eq!(None, bigrams.get(&("why".to_owned(), "because".to_owned())));
+        assert_eq!(3, bigrams.len());
+    }
+
+    #[test]
+    fn no_bigrams_for_single_word() {
+        let (table, bigrams) = form_tables("hello".as_bytes());
+        assert_eq!(1, table.len());
+        assert!(bigrams.is_empty());
+    }
+}
+
+#[cfg(test)]
+mod alphabet_tests {
+    use super::{alphabet_of, form_table, increment_word, CountTable};
+
+    #[test]
+    fn alphabet_from_corpus() {
+        let table = form_table("Café crème, déjà vu. Straße!".as_bytes());
+        assert_eq!(alphabet_of(&table), vec!['a', 'c', 'd', 'e', 'f', 'j', 'm', 'r', 's', 't', 'u', 'v',
+                                             'ß', 'à', 'è', 'é']);
+    }
+
+    #[test]
+    fn rare_characters_dropped() {
+        let mut table = CountTable::new();
+        table.insert("ab".to_owned(), 100000);
+        increment_word(&mut table, "a\u{2603}".to_owned());
+        assert_eq!(alphabet_of(&table), vec!['a', 'b']);
+    }
+}
+
+#[cfg(test)]
+mod parse_input_tests {
+    use super::{parse_input};
+
+    #[test]
+    fn parse_input_strip_test() {
+        assert_parse_eq("hello!", "hello");
+        assert_parse_eq("hello!?!?!", "hello");
+        assert_parse_eq("Hello\"", "hello");
+    }
+
+    #[test]
+    fn parse_input_replace_test() {
+        assert_parse_eq("hello,world", "hello world");
+        assert_parse_eq("hello.world", "hello world");
+    }
+
+    #[test]
+    fn parse_input_lower_test() {
+        assert_parse_eq("HELLO", "hello");
+        assert_parse_eq("hello", "hello");
+        assert_parse_eq("HelLo", "hello");
+    }
+
+    fn assert_parse_eq(string: &str, result: &str) {
+        assert_eq!(result.to_owned(), parse_input(string.to_owned()));
+    }
+}
+
+
+#[cfg(test)]
+mod increment_word_tests {
+    use super::{increment_word, CountTable};
+
+    #[test]
+    fn inserts_if_empty() {
+        let mut h = CountTable::new();
+        increment_word(&mut h, "one".to_owned());
+
+        assert_eq!(Some(&1), h.get("one"));
+        assert_eq!(1, h.len());
+    }
+
+    #[test]
+    fn increments_if_present() {
+        let mut under_test = fixture();
+        let mut expected   = fixture();
+
+        increment_word(&mut under_test, "three".to_owned());
+        expected.insert("three".to_owned(), 4);
+
+        assert_eq!(expected, under_test);
+    }
+
+    #[test]
+    fn insert_if_absent() {
+        let mut under_test = fixture();
+        let mut expected   = fixture();
+
+        increment_word(&mut under_test, "one".to_owned());
+        expected.insert("one".to_owned(), 1);
+
+        assert_eq!(expected, under_test);
+    }
+
+    fn fixture() -> CountTable {
+        let mut h = CountTable::new();
+        h.insert("two".to_owned(), 2);
+        h.insert("three".to_owned(), 3);
+
+        assert_eq!(None, h.get("one"));
+        assert_eq!(Some(&2), h.get("two"));
+        assert_eq!(Some(&3), h.get("three"));
+        assert_eq!(2, h.len());
+        h
+    }
+}
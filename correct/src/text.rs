@@ -0,0 +1,478 @@
+#![doc="
+    Full-text correction. Splits running prose into word and non-word tokens, corrects each word
+    and puts the text back together with the original punctuation, whitespace and
+    capitalization pattern. Every change is recorded with its byte offset in the input.
+
+    With a context model, each word is chosen using its neighbouring words as well, which also
+    lets real-word errors (a known word used in the wrong place) be corrected.
+
+    Two words separated by a single space are joined when they look like one word typed apart
+    (see compound::join); a run-together word may be split in turn, like any other correction.
+
+    Assumptions:
+        - A word is a run of letters, possibly with apostrophes inside (don't, o'clock)
+        - Runs mixing letters and digits or underscores (abc123, snake_case) are left alone
+        - Words are looked up the same way training counts them: lowercased, apostrophes removed
+"]
+
+use std::collections::HashMap;
+use std::fmt;
+
+use compound::join;
+use context::ContextModel;
+use corrector::{Candidate, Corrector};
+
+/// Capitalization pattern of a word
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Case {
+    Lower,
+    Upper,
+    Title,
+    Mixed,
+}
+
+impl Case {
+    pub fn of(word: &str) -> Case {
+        let letters: Vec<char> = word.chars().filter(|c| c.is_alphabetic()).collect();
+        let upper = letters.iter().filter(|c| c.is_uppercase()).count();
+
+        if upper == 0 {
+            Case::Lower
+        } else if letters[0].is_uppercase() && upper == 1 {
+            Case::Title
+        } else if upper == letters.len() {
+            Case::Upper
+        } else {
+            Case::Mixed
+        }
+    }
+
+    /// Applies this pattern onto a lowercase word. Mixed case isn't a pattern that can be
+    /// applied to any word, so it is left lowercase here; see restore_case.
+    pub fn apply(&self, word: &str) -> String {
+        match *self {
+            Case::Lower | Case::Mixed => word.to_owned(),
+            Case::Upper => word.to_uppercase(),
+            Case::Title => {
+                let mut chars = word.chars();
+                match chars.next() {
+                    Some(first) => first.to_uppercase().chain(chars).collect(),
+                    None => String::new(),
+                }
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Token<'a> {
+    pub offset: usize,
+    pub text: &'a str,
+    pub is_word: bool,
+}
+
+/// A single replacement made in the text
+#[derive(Clone, Debug, PartialEq)]
+pub struct Change {
+    pub offset: usize,
+    pub original: String,
+    pub replacement: String,
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {} -> {}", self.offset, self.original, self.replacement)
+    }
+}
+
+/// A word the corrector doesn't accept, with its ranked suggestions (lowercase keys)
+#[derive(Clone, Debug, PartialEq)]
+pub struct Misspelling {
+    pub offset: usize,
+    pub word: String,
+    pub suggestions: Vec<Candidate>,
+}
+
+impl Misspelling {
+    /// Byte offset just past the word
+    pub fn end(&self) -> usize {
+        self.offset + self.word.len()
+    }
+
+    /// A suggestion written with the capitalization of the misspelled word
+    pub fn replacement(&self, suggestion: &Candidate) -> String {
+        restore_case(&self.word, &suggestion.word)
+    }
+}
+
+/// Lookup key for a word, matching what the training phase counts
+pub fn normalize(word: &str) -> String {
+    word.chars().filter(|&c| c != '\'').flat_map(|c| c.to_lowercase()).collect()
+}
+
+/// A lowercase correction written with the capitalization of the typed word. Lower, Title
+/// and UPPER case are applied as patterns; mixed case is copied letter by letter, so the
+/// letters past a typo in iPhnoe or McDonlad keep their case as long as the lengths line up.
+pub fn restore_case(typed: &str, correction: &str) -> String {
+    match Case::of(typed) {
+        Case::Mixed => {
+            let upper: Vec<bool> = typed.chars().filter(|c| c.is_alphabetic()).map(|c| c.is_uppercase()).collect();
+            let mut letter = 0;
+            let mut restored = String::new();
+            for c in correction.chars() {
+                if c.is_alphabetic() {
+                    if upper.get(letter) == Some(&true) {
+                        restored.extend(c.to_uppercase());
+                    } else {
+                        restored.push(c);
+                    }
+                    letter += 1;
+                } else {
+                    restored.push(c);
+                }
+            }
+            restored
+        }
+        case => case.apply(correction),
+    }
+}
+
+/// Splits text into tokens that concatenate back to the original text
+pub fn tokenize(text: &str) -> Vec<Token<'_>> {
+    let mut tokens = Vec::new();
+    let mut start = 0;
+    let mut in_word = false;
+
+    for (i, c) in text.char_indices() {
+        let wordy = c.is_alphanumeric() || c == '_' || (c == '\'' && in_word && next_is_alphabetic(text, i));
+        if wordy != in_word && i > start {
+            tokens.push(make_token(text, start, i, in_word));
+            start = i;
+        }
+        in_word = wordy;
+    }
+    if start < text.len() {
+        tokens.push(make_token(text, start, text.len(), in_word));
+    }
+    tokens
+}
+
+fn next_is_alphabetic(text: &str, apostrophe: usize) -> bool {
+    text[apostrophe + 1..].chars().next().is_some_and(|c| c.is_alphabetic())
+}
+
+fn make_token(text: &str, start: usize, end: usize, wordy: bool) -> Token<'_> {
+    let slice = &text[start..end];
+    Token {
+        offset: start,
+        text: slice,
+        is_word: wordy && slice.chars().all(|c| c.is_alphabetic() || c == '\''),
+    }
+}
+
+/// Corrects every word of the text, returning the corrected text and the list of changes
+pub fn correct_text(text: &str, corrector: &Corrector) -> (String, Vec<Change>) {
+    let choose = |token: &Token, _: Option<&str>, _: Option<&str>| {
+        let key = normalize(token.text);
+        if corrector.is_known(&key) {
+            return None;
+        }
+        corrector.correct(&key)
+    };
+    rewrite(text, corrector, choose, |first, second| join(first, second, corrector, None))
+}
+
+/// Like correct_text, but scores candidates against the previous (already corrected) and the
+/// next word of the text
+pub fn correct_text_in_context(text: &str, corrector: &Corrector, context: &ContextModel)
+        -> (String, Vec<Change>) {
+    let choose = |token: &Token, previous: Option<&str>, next: Option<&str>| {
+        context.choose(corrector, previous, &normalize(token.text), next)
+    };
+    rewrite(text, corrector, choose, |first, second| join(first, second, corrector, Some(context)))
+}
+
+/// Finds the words of the text the corrector doesn't accept, without changing anything
+pub fn misspellings(text: &str, corrector: &Corrector) -> Vec<Misspelling> {
+    let ignored = corrector.ignore_list().spans(text);
+    tokenize(text).into_iter()
+        .filter(|t| t.is_word && !is_skipped(t, corrector, &ignored))
+        .filter_map(|t| {
+            let key = normalize(t.text);
+            if corrector.is_known(&key) {
+                return None;
+            }
+            Some(Misspelling { offset: t.offset, word: t.text.to_owned(), suggestions: corrector.suggestions(&key) })
+        })
+        .collect()
+}
+
+fn is_skipped(token: &Token, corrector: &Corrector, ignored: &[(usize, usize)]) -> bool {
+    let end = token.offset + token.text.len();
+    corrector.is_exempt(token.text) || ignored.iter().any(|&(start, stop)| start < end && token.offset < stop)
+}
+
+/// Rebuilds the text, asking `choose` for a replacement (a lowercase key) for every word given
+/// the keys of the previous and next words, and `join` whether two words separated by a space
+/// are one word. Words the corrector exempts, or that are part of a span matched by its ignore
+/// list (a URL, an email), are left alone.
+fn rewrite<F, J>(text: &str, corrector: &Corrector, mut choose: F, mut join: J) -> (String, Vec<Change>)
+    where F: FnMut(&Token, Option<&str>, Option<&str>) -> Option<String>,
+          J: FnMut(&str, &str) -> Option<String>
+{
+    let ignored = corrector.ignore_list().spans(text);
+    let tokens = tokenize(text);
+    let mut keys: Vec<String> = tokens.iter()
+        .filter(|t| t.is_word)
+        .map(|t| normalize(t.text))
+        .collect();
+
+    // Joins first, so the words around a joined pair see the joined word as their neighbour.
+    // joins[i] is the joined word starting at token i, which spans tokens i to i + 2.
+    let mut joins = HashMap::new();
+    let mut index = 0;
+    let mut i = 0;
+    while i < tokens.len() {
+        if !tokens[i].is_word {
+            i += 1;
+            continue;
+        }
+        let joined = match (tokens.get(i + 1), tokens.get(i + 2)) {
+            (Some(space), Some(second)) if space.text == " " && second.is_word
+                    && !is_skipped(&tokens[i], corrector, &ignored) && !is_skipped(second, corrector, &ignored) => {
+                join(&keys[index], &keys[index + 1])
+            }
+            _ => None,
+        };
+        match joined {
+            Some(joined) => {
+                keys[index] = joined.clone();
+                keys[index + 1] = joined.clone();
+                joins.insert(i, joined);
+                index += 2;
+                i += 3;
+            }
+            None => {
+                index += 1;
+                i += 1;
+            }
+        }
+    }
+
+    let mut result = String::with_capacity(text.len());
+    let mut changes = Vec::new();
+    let mut index = 0;
+    let mut i = 0;
+
+    while i < tokens.len() {
+        let token = &tokens[i];
+        let joined = joins.get(&i);
+        i += 1;
+        if !token.is_word {
+            result.push_str(token.text);
+            continue;
+        }
+        if let Some(joined) = joined {
+            let second = &tokens[i + 1];
+            let replacement = restore_case(token.text, joined);
+            result.push_str(&replacement);
+            changes.push(Change {
+                offset: token.offset,
+                original: text[token.offset..second.offset + second.text.len()].to_owned(),
+                replacement,
+            });
+            index += 2;
+            i += 2;
+            continue;
+        }
+        if is_skipped(token, corrector, &ignored) {
+            result.push_str(token.text);
+            index += 1;
+            continue;
+        }
+
+        let previous = if index > 0 { Some(keys[index - 1].as_str()) } else { None };
+        let next = keys.get(index + 1).map(|k| k.as_str());
+        match choose(token, previous, next) {
+            Some(best) => {
+                let replacement = restore_case(token.text, &best);
+                result.push_str(&replacement);
+                changes.push(Change {
+                    offset: token.offset,
+                    original: token.text.to_owned(),
+                    replacement,
+                });
+                keys[index] = best;
+            }
+            None => result.push_str(token.text),
+        }
+        index += 1;
+    }
+    (result, changes)
+}
+
+#[cfg(test)]
+mod text_tests {
+    use super::{correct_text, correct_text_in_context, misspellings, normalize, restore_case, tokenize, Case,
+                Change};
+    use context::ContextModel;
+    use corrector::Corrector;
+    use table::{form_table, form_tables};
+
+    fn corrector() -> Corrector {
+        Corrector::new(form_table("the quick brown fox jumps over the lazy dog don't".as_bytes()))
+    }
+
+    #[test]
+    fn case_of_test() {
+        assert_eq!(Case::of("hello"), Case::Lower);
+        assert_eq!(Case::of("Hello"), Case::Title);
+        assert_eq!(Case::of("HELLO"), Case::Upper);
+        assert_eq!(Case::of("hELLo"), Case::Mixed);
+        assert_eq!(Case::of("I"), Case::Title);
+    }
+
+    #[test]
+    fn case_apply_test() {
+        assert_eq!(Case::Title.apply("hello"), "Hello");
+        assert_eq!(Case::Upper.apply("hello"), "HELLO");
+        assert_eq!(Case::Lower.apply("hello"), "hello");
+        assert_eq!(Case::Mixed.apply("hello"), "hello");
+    }
+
+    #[test]
+    fn restore_case_test() {
+        assert_eq!(restore_case("helo", "hello"), "hello");
+        assert_eq!(restore_case("Helo", "hello"), "Hello");
+        assert_eq!(restore_case("HELO", "hello"), "HELLO");
+        assert_eq!(restore_case("Teh", "the"), "The");
+        // Mixed case is copied letter by letter
+        assert_eq!(restore_case("HEllo", "hello"), "HEllo");
+        assert_eq!(restore_case("mcDonlad", "mcdonald"), "mcDonald");
+        assert_eq!(restore_case("iPhnoe", "iphone"), "iPhone");
+        assert_eq!(restore_case("HElo", "hello"), "HEllo");
+        assert_eq!(restore_case("Inthe", "in the"), "In the");
+    }
+
+    #[test]
+    fn is_accepted_ignores_case() {
+        let corrector = corrector();
+        assert!(corrector.is_accepted("the"));
+        assert!(corrector.is_accepted("The"));
+        assert!(corrector.is_accepted("THE"));
+        assert!(corrector.is_accepted("tHe"));
+        assert!(!corrector.is_accepted("Teh"));
+        // Exemptions are checked as typed: CamelCase is ignored, whatever its letters
+        assert!(corrector.is_accepted("SomeThing"));
+    }
+
+    #[test]
+    fn tokenize_round_trips() {
+        let text = "Hello,  world! It's 3pm -- \"quoted\"\n";
+        let joined: String = tokenize(text).iter().map(|t| t.text).collect();
+        assert_eq!(joined, text);
+    }
+
+    #[test]
+    fn tokenize_words() {
+        let words: Vec<&str> = tokenize("It's a snake_case abc123 'quote'").iter()
+            .filter(|t| t.is_word)
+            .map(|t| t.text)
+            .collect();
+        assert_eq!(words, vec!["It's", "a", "quote"]);
+    }
+
+    #[test]
+    fn normalize_test() {
+        assert_eq!(normalize("Don't"), "dont");
+    }
+
+    #[test]
+    fn correct_text_preserves_layout() {
+        let (text, _) = correct_text("Teh quikc  BROWN fox,\njumsp over the lazy dgo.", &corrector());
+        assert_eq!(text, "The quick  BROWN fox,\njumps over the lazy dog.");
+
+        let (text, _) = correct_text("THE QUIKC Brwon", &corrector());
+        assert_eq!(text, "THE QUICK Brown");
+    }
+
+    #[test]
+    fn correct_text_reports_offsets() {
+        let (_, changes) = correct_text("the quikc fox, teh dog", &corrector());
+        assert_eq!(changes, vec![
+            Change { offset: 4, original: "quikc".to_owned(), replacement: "quick".to_owned() },
+            Change { offset: 15, original: "teh".to_owned(), replacement: "the".to_owned() },
+        ]);
+        assert_eq!(changes[0].to_string(), "4: quikc -> quick");
+    }
+
+    #[test]
+    fn correct_text_leaves_unknowns_and_known() {
+        let (text, changes) = correct_text("Don't zzzzzzzz 42", &corrector());
+        assert_eq!(text, "Don't zzzzzzzz 42");
+        assert!(changes.is_empty());
+    }
+
+    #[test]
+    fn correct_text_skips_exempt_words() {
+        let mut corrector = corrector();
+        corrector.personal_mut().add("jumsp").unwrap();
+        let (text, changes) = correct_text("Jumsp over https://the.qiuck.fox/dgo the teh@fox.com, HashMapp teh",
+                                           &corrector);
+        assert_eq!(text, "Jumsp over https://the.qiuck.fox/dgo the teh@fox.com, HashMapp the");
+        assert_eq!(changes.len(), 1);
+    }
+
+    #[test]
+    fn correct_text_in_context_fixes_real_words() {
+        let (table, bigrams) = form_tables("They lost their keys. I put it over there. \
+                                            Their house is over there. We saw their keys there.".as_bytes());
+        let corrector = Corrector::new(table);
+        let context = ContextModel::new(bigrams);
+
+        let (text, changes) = correct_text_in_context("They lost there keys.", &corrector, &context);
+        assert_eq!(text, "They lost their keys.");
+        assert_eq!(changes[0].offset, 10);
+
+        let (text, _) = correct_text_in_context("Thier house is over there.", &corrector, &context);
+        assert_eq!(text, "Their house is over there.");
+    }
+
+    #[test]
+    fn misspellings_test() {
+        let found = misspellings("Teh fox, zzzzzzzz www.qiuck.com dog", &corrector());
+        assert_eq!(found.len(), 2);
+        assert_eq!((found[0].offset, found[0].end(), found[0].word.as_str()), (0, 3, "Teh"));
+        assert_eq!(found[0].suggestions[0].word, "the");
+        assert_eq!(found[0].replacement(&found[0].suggestions[0]), "The");
+        assert_eq!(found[1].word, "zzzzzzzz");
+        assert!(found[1].suggestions.is_empty());
+    }
+
+    #[test]
+    fn correct_text_splits_and_joins() {
+        let corrector = Corrector::new(form_table("we walked together in the park with a notebook".as_bytes()));
+        let (text, changes) = correct_text("Inthe park we walked to gether.", &corrector);
+        assert_eq!(text, "In the park we walked together.");
+        assert_eq!(changes, vec![
+            Change { offset: 0, original: "Inthe".to_owned(), replacement: "In the".to_owned() },
+            Change { offset: 21, original: "to gether".to_owned(), replacement: "together".to_owned() },
+        ]);
+    }
+
+    #[test]
+    fn correct_text_in_context_joins_known_words() {
+        let (table, bigrams) = form_tables("my notebook is on the desk. a note and a book. \
+                                            a part of the book is apart from a part of the note".as_bytes());
+        let corrector = Corrector::new(table);
+        let context = ContextModel::new(bigrams);
+
+        let (text, _) = correct_text("a note book", &corrector);
+        assert_eq!(text, "a note book");
+        let (text, changes) = correct_text_in_context("My note book is on the desk.", &corrector, &context);
+        assert_eq!(text, "My notebook is on the desk.");
+        assert_eq!(changes.len(), 1);
+        let (text, _) = correct_text_in_context("a part of the book", &corrector, &context);
+        assert_eq!(text, "a part of the book");
+    }
+}
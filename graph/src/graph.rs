@@ -1,93 +1,221 @@
-use std::collections::{HashSet, HashMap};
-use std::hash::{Hash, SipHasher, Hasher};
-
-#[derive(Eq, Debug)]
-pub struct Vertex<'a, T> {
-    adj: HashSet<&'a Vertex<'a, T>>,
-    key : T<'a>',
+#![doc="
+    Undirected graph over any key type that can be hashed, compared and cloned. Vertices are
+    owned by the graph and found by key; each one keeps the keys of its neighbours, so there
+    are no references between vertices to keep alive or borrow mutably.
+
+    Assumptions:
+        - Simple graph: no self-loops and at most one edge between two vertices
+        - Keys are unique
+        - Any shortest path (fewest edges) from source to destination is fine
+"]
+
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::hash::Hash;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Vertex<K: Hash + Eq> {
+    key: K,
+    adj: HashSet<K>,
 }
 
-impl<'a, T : Eq> Vertex<'a, T> {
-    /// Creates a new node with the given name
-    pub fn new(key: T) -> Vertex<'a, T> {
+impl<K: Hash + Eq + Clone> Vertex<K> {
+    /// Creates a new vertex with no neighbours
+    pub fn new(key: K) -> Vertex<K> {
         Vertex {
+            key,
             adj: HashSet::new(),
-            key: key,
         }
     }
 
-    fn add_neighbor<'b, 'c>(a : &Vertex<'b, T>, b : &Vertex<'c, T>) {
-        Vertex::add_link(a, b);
-        Vertex::add_link(b, a);
+    pub fn key(&self) -> &K {
+        &self.key
     }
 
-    fn add_link<'b, 'c>(a : &Vertex<'b, T>, b : &Vertex<'c, T>) {
-        a.adj.insert(b);
+    /// Keys of the vertices sharing an edge with this one
+    pub fn neighbors(&self) -> &HashSet<K> {
+        &self.adj
     }
-}
-
 
-impl<'a, T : Eq> PartialEq for Vertex<'a, T> {
-    fn eq(&self, other : &Vertex<T>) -> bool {
-        (self.key == other.key)
+    /// @return bool - whether the link is new
+    fn add_link(&mut self, key: K) -> bool {
+        self.adj.insert(key)
     }
+}
 
-    fn ne(&self, other : &Vertex<T>) -> bool{
-        (self.key != other.key)
-    }
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Graph<K: Hash + Eq> {
+    vertices: HashMap<K, Vertex<K>>,
 }
 
-impl<'a, T : Eq> Hash for Vertex<'a, T> {
-    fn hash<H : Hasher>(&self, state: &mut H) {
-        self.key.hash(state);
+impl<K: Hash + Eq + Clone> Default for Graph<K> {
+    fn default() -> Graph<K> {
+        Graph::new()
     }
 }
 
+impl<K: Hash + Eq + Clone> Graph<K> {
+    pub fn new() -> Graph<K> {
+        Graph {
+            vertices: HashMap::new(),
+        }
+    }
 
+    /// Adds a vertex, unless one with the same key exists
+    /// @param key : K - key of the new vertex
+    ///
+    /// @return bool - whether the vertex is new
+    pub fn add_vertex(&mut self, key: K) -> bool {
+        if self.vertices.contains_key(&key) {
+            return false;
+        }
+        self.vertices.insert(key.clone(), Vertex::new(key));
+        true
+    }
 
-
-pub struct Graph<'a, 'b, T> {
-    vertex_map: Option<HashMap<String, Vertex<'b, T>>>,
-}
-
-
-impl<'a, 'b, T> Graph<'a, 'b, T> {
-    fn new() -> Graph<'a, 'b, T> {
-        Graph {
-            vertex_map: HashMap::new(),
+    /// Adds an undirected edge between two existing vertices. Self-loops are refused.
+    /// @param a : &K - key of a vertex in the graph
+    /// @param b : &K - key of a vertex in the graph
+    ///
+    /// @return bool - whether a new edge was added
+    pub fn add_edge(&mut self, a: &K, b: &K) -> bool {
+        if a == b || !self.vertices.contains_key(a) || !self.vertices.contains_key(b) {
+            return false;
         }
+        let added = self.vertex_mut(a).add_link(b.clone());
+        self.vertex_mut(b).add_link(a.clone());
+        added
     }
 
-    fn add_vertex(self, name: String) {
-        unimplemented!();
+    fn vertex_mut(&mut self, key: &K) -> &mut Vertex<K> {
+        self.vertices.get_mut(key).expect("vertex checked to exist")
+    }
 
-        // match self.vertex_map[name] {
-        //     None => {
-        //         self.vertex_map[name] = Vertex::new(name);
-        //     }
-        // }
+    pub fn vertex(&self, key: &K) -> Option<&Vertex<K>> {
+        self.vertices.get(key)
     }
 
-    fn add_edge(self, src: String, dst: String) {
-        unimplemented!();
+    pub fn contains(&self, key: &K) -> bool {
+        self.vertices.contains_key(key)
+    }
 
-        // let source = self.vertex_map[src];
-        // let destination = self.vertex_map[dst];
-        // self.vertex_map[src].add_neighbor(destination);
-        // self.vertex_map[src].add_neighbor(source);
+    /// Number of vertices
+    pub fn len(&self) -> usize {
+        self.vertices.len()
     }
 
-    fn search_path(src: String, dst:String) -> Option<Vec<&'b Vertex<'b, T>>> {
-        unimplemented!();
-        // TODO;
+    pub fn is_empty(&self) -> bool {
+        self.vertices.is_empty()
+    }
+
+    /// A shortest path from `src` to `dst`, both included, found with breadth-first search
+    /// @param src : &K - key of a vertex in the graph
+    /// @param dst : &K - key of a vertex in the graph
+    ///
+    /// @return Option<Vec<&K>> - the keys along the path, None if either vertex is missing or
+    /// they aren't connected
+    pub fn search_path(&self, src: &K, dst: &K) -> Option<Vec<&K>> {
+        let (src, _) = self.vertices.get_key_value(src)?;
+        let (dst, _) = self.vertices.get_key_value(dst)?;
+
+        // vertex -> the vertex it was reached from
+        let mut parents: HashMap<&K, &K> = HashMap::new();
+        let mut visited: HashSet<&K> = HashSet::new();
+        let mut queue: VecDeque<&K> = VecDeque::new();
+        visited.insert(src);
+        queue.push_back(src);
+
+        while let Some(current) = queue.pop_front() {
+            if current == dst {
+                let mut path = vec![current];
+                let mut node = current;
+                while let Some(&parent) = parents.get(node) {
+                    path.push(parent);
+                    node = parent;
+                }
+                path.reverse();
+                return Some(path);
+            }
+            for neighbor in &self.vertices[current].adj {
+                if visited.insert(neighbor) {
+                    parents.insert(neighbor, current);
+                    queue.push_back(neighbor);
+                }
+            }
+        }
+        None
     }
 }
 
 #[cfg(test)]
 mod test {
+    use super::Graph;
+
+    fn line(keys: &[u32]) -> Graph<u32> {
+        let mut graph = Graph::new();
+        for &key in keys {
+            graph.add_vertex(key);
+        }
+        for pair in keys.windows(2) {
+            graph.add_edge(&pair[0], &pair[1]);
+        }
+        graph
+    }
+
     #[test]
     fn test_graph() {
         assert_eq!(1, 1);
     }
 
+    #[test]
+    fn add_vertex_keeps_keys_unique() {
+        let mut graph = Graph::new();
+        assert!(graph.is_empty());
+        assert!(graph.add_vertex("a".to_string()));
+        assert!(graph.add_vertex("b".to_string()));
+        assert!(!graph.add_vertex("a".to_string()));
+        assert_eq!(graph.len(), 2);
+        assert!(graph.contains(&"b".to_string()));
+    }
+
+    #[test]
+    fn add_edge_is_undirected_and_simple() {
+        let mut graph = line(&[1, 2]);
+        graph.add_vertex(3);
+        assert!(graph.add_edge(&2, &3));
+        assert!(!graph.add_edge(&3, &2));
+        assert!(!graph.add_edge(&1, &1));
+        assert!(!graph.add_edge(&1, &4));
+        assert!(graph.vertex(&3).unwrap().neighbors().contains(&2));
+        assert_eq!(graph.vertex(&2).unwrap().neighbors().len(), 2);
+        assert_eq!(graph.vertex(&2).unwrap().key(), &2);
+    }
+
+    #[test]
+    fn search_path_finds_shortest() {
+        let mut graph = line(&[1, 2, 3, 4, 5]);
+        assert_eq!(graph.search_path(&1, &5), Some(vec![&1, &2, &3, &4, &5]));
+        assert_eq!(graph.search_path(&4, &2), Some(vec![&4, &3, &2]));
+        graph.add_edge(&1, &4);
+        assert_eq!(graph.search_path(&1, &5), Some(vec![&1, &4, &5]));
+        assert_eq!(graph.search_path(&3, &3), Some(vec![&3]));
+    }
+
+    #[test]
+    fn search_path_without_path() {
+        let mut graph = line(&[1, 2]);
+        graph.add_vertex(3);
+        assert_eq!(graph.search_path(&1, &3), None);
+        assert_eq!(graph.search_path(&1, &9), None);
+    }
+
+    #[test]
+    fn tuple_keys() {
+        let mut graph: Graph<(i32, i32)> = Graph::new();
+        for &cell in &[(0, 0), (0, 1), (1, 1)] {
+            graph.add_vertex(cell);
+        }
+        graph.add_edge(&(0, 0), &(0, 1));
+        graph.add_edge(&(0, 1), &(1, 1));
+        assert_eq!(graph.search_path(&(0, 0), &(1, 1)).map(|p| p.len()), Some(3));
+    }
 }
@@ -1,11 +1,17 @@
-#[doc="
+#![doc="
     @authors: Adel and James, Whang and Lahlou.
 
     @description:
+        Reads a graph file where each line is a chain of vertex names, consecutive names sharing
+        an edge, then answers queries from stdin: each line of names asks for a path between every
+        consecutive pair.
 
     @comments:
+        - Names are runs of word characters; anything else separates them
 
     @assumptions:
+        - A name alone on a line is a vertex with no edges
+        - We alert the user when there is no path
 "]
 
 extern crate graph;
@@ -13,31 +19,32 @@ extern crate regex;
 use std::io::{BufRead, BufReader, Read, stdin};
 use std::fs::{File};
 use regex::{Regex};
-use graph::{Graph, Vertex};
+use graph::{Graph};
 
 
-const SPLIT_WHITESPACE : Regex = Regex::new(r"\w+").unwrap();
+/// Pattern for a vertex name. A Regex can't be built in a const, so main compiles it once.
+const WORD_PATTERN : &str = r"\w+";
 
 
 fn main() {
+    let words = Regex::new(WORD_PATTERN).unwrap();
     let inputfile = process_commandline();
-    let graph = process_file(inputfile);
-    handle_queries(stdin(), &graph);
+    let graph = process_file(inputfile, &words);
+    handle_queries(stdin(), &graph, &words);
 }
 
-fn process_file<R : Read>(input: R) -> Graph<String>{
+fn process_file<R : Read>(input: R, words: &Regex) -> Graph<String> {
     let mut graph : Graph<String> = Graph::new();
-    let mut previous : Option<String> = None;
     let reader = BufReader::new(input);
 
     for line in reader.lines() {
-        let tokens = split_whitespace(&line.unwrap());
+        let mut previous : Option<String> = None;
 
-        for node in tokens {
-            graph.add_vertex(node);
+        for node in split_words(&line.unwrap(), words) {
+            graph.add_vertex(node.clone());
 
             if let Some(pnode) = previous {
-                graph.add_edge(pnode, node);
+                graph.add_edge(&pnode, &node);
             }
 
             previous = Some(node);
@@ -48,28 +55,30 @@ fn process_file<R : Read>(input: R) -> Graph<String>{
 }
 
 
-fn handle_queries<R: Read>(input : R, graph : &Graph<String>) {
-    let mut previous  : Option<String> = None;
+fn handle_queries<R: Read>(input : R, graph : &Graph<String>, words: &Regex) {
     let reader = BufReader::new(input);
 
     for line in reader.lines() {
-        let mut path_string = String::new();
-        let tokens = split_whitespace(&line.unwrap());
+        for answer in answer_query(graph, &line.unwrap(), words) {
+            println!("{}", answer);
+        }
+    }
+}
 
-        for node in tokens {
-            if let Some(pnode) = previous {
-                if let Some(sub_path_string) = Graph::search_path(pnode, node) {
-                    path_string.push_str(&sub_path_string);
-                }
-            }
+/// One answer for each consecutive pair of names in the query line
+fn answer_query(graph : &Graph<String>, line : &str, words: &Regex) -> Vec<String> {
+    let tokens = split_words(line, words);
 
-            previous = Some(node);
+    tokens.windows(2).map(|pair| {
+        match graph.search_path(&pair[0], &pair[1]) {
+            Some(path) => path.iter().map(|node| node.as_str()).collect::<Vec<_>>().join(" "),
+            None => format!("There is no path from {} to {}", pair[0], pair[1]),
         }
-    }
+    }).collect()
 }
 
-fn split_whitespace(line : &String) -> Vec<String> {
-    line.split(&SPLIT_WHITESPACE).collect::<Vec<_>>()
+fn split_words(line : &str, words: &Regex) -> Vec<String> {
+    words.find_iter(line).map(|m| m.as_str().to_string()).collect()
 }
 
 fn process_commandline() -> BufReader<File> {
@@ -79,27 +88,62 @@ fn process_commandline() -> BufReader<File> {
         panic!("usage: cargo run graph_file.dat");
     }
 
-    let filename = args[1];
-    BufReader::new(open_file(filename));
+    BufReader::new(open_file(&args[1], "Couldn't open file"))
 }
 
-fn open_file(filename: &String, panic_message : &str) -> File{
+fn open_file(filename: &str, panic_message : &str) -> File{
     if let Ok(file) = File::open(filename) {
         file
     } else {
-        panic!(panic_message);
+        panic!("{}", panic_message);
     }
 }
 
 
 #[cfg(test)]
 mod graph_usage_tests{
+    use regex::Regex;
+    use super::{WORD_PATTERN, process_file};
+    use graph::Graph;
+
+    fn build(input : &str) -> Graph<String> {
+        process_file(input.as_bytes(), &Regex::new(WORD_PATTERN).unwrap())
+    }
+
     mod graph_building_test{
+        use super::build;
+
+        #[test]
+        fn chains_names_on_a_line() {
+            let graph = build("a b c\nc d\ne");
+            assert_eq!(graph.len(), 5);
+            let c = graph.vertex(&"c".to_string()).unwrap();
+            assert_eq!(c.neighbors().len(), 2);
+            assert!(graph.vertex(&"e".to_string()).unwrap().neighbors().is_empty());
+        }
 
+        #[test]
+        fn lines_are_not_chained() {
+            let graph = build("a b\nc, d");
+            assert!(!graph.vertex(&"b".to_string()).unwrap().neighbors().contains("c"));
+            assert!(graph.vertex(&"c".to_string()).unwrap().neighbors().contains("d"));
+        }
     }
 
 
     mod graph_querying_test {
-
+        use super::build;
+        use super::super::answer_query;
+        use regex::Regex;
+        use WORD_PATTERN;
+
+        #[test]
+        fn answers_each_pair() {
+            let graph = build("a b c\nc d\ne");
+            let words = Regex::new(WORD_PATTERN).unwrap();
+            assert_eq!(answer_query(&graph, "a d e", &words),
+                       vec!["a b c d".to_string(), "There is no path from d to e".to_string()]);
+            assert!(answer_query(&graph, "a", &words).is_empty());
+        }
     }
 }
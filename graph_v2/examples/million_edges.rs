@@ -0,0 +1,126 @@
+#![doc="
+    Builds a random graph with a million edges and times find_path between random pairs of
+    vertices. Memory is the resident set size the process grew by while building, read from
+    /proc/self/status, so it is only reported on Linux.
+
+    With --baseline, the same graph and queries run against the old Rc<RefCell> graph kept in
+    examples/baseline, for comparison. Run each mode in its own process so the memory figures
+    don't mix.
+
+    Run with: cargo run --release --example million_edges [--baseline] [vertices edges queries]
+"]
+
+extern crate graph_v2;
+use std::fs::File;
+use std::io::Read;
+use std::time::Instant;
+use graph_v2::graph::Graph;
+
+#[path = "baseline/rc_graph.rs"]
+#[allow(warnings, clippy::all)]
+mod rc_graph;
+
+static USAGE: &str = "usage: million_edges [--baseline] [vertices edges queries]";
+
+/// What the benchmark needs from a graph, so both implementations run the same code
+trait Benched {
+    fn add_vertex(&mut self, key: String);
+    fn add_edge(&mut self, a: &str, b: &str) -> bool;
+    fn find_path(&self, src: String, dst: String) -> Vec<String>;
+    fn len(&self) -> usize;
+}
+
+impl Benched for Graph {
+    fn add_vertex(&mut self, key: String) { Graph::add_vertex(self, key) }
+    fn add_edge(&mut self, a: &str, b: &str) -> bool { Graph::add_edge(self, a, b) }
+    fn find_path(&self, src: String, dst: String) -> Vec<String> { Graph::find_path(self, src, dst) }
+    fn len(&self) -> usize { Graph::len(self) }
+}
+
+impl Benched for rc_graph::Graph {
+    fn add_vertex(&mut self, key: String) { rc_graph::Graph::add_vertex(self, key) }
+    fn add_edge(&mut self, a: &str, b: &str) -> bool { rc_graph::Graph::add_edge(self, &a.to_owned(), &b.to_owned()) }
+    fn find_path(&self, src: String, dst: String) -> Vec<String> { rc_graph::Graph::find_path(self, src, dst) }
+    fn len(&self) -> usize { rc_graph::Graph::len(self) }
+}
+
+/// Small linear congruential generator, so every run builds the same graph without a dependency
+struct Lcg(u64);
+
+impl Lcg {
+    fn below(&mut self, n: usize) -> usize {
+        self.0 = self.0.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+        ((self.0 >> 33) % n as u64) as usize
+    }
+}
+
+/// Resident set size in kilobytes, if the platform reports it
+fn resident_kb() -> Option<usize> {
+    let mut status = String::new();
+    File::open("/proc/self/status").ok()?.read_to_string(&mut status).ok()?;
+    let line = status.lines().find(|line| line.starts_with("VmRSS:"))?;
+    line.split_whitespace().nth(1)?.parse().ok()
+}
+
+fn arg(args: &[String], i: usize, default: usize) -> usize {
+    args.get(i).map_or(default, |a| a.parse().expect(USAGE))
+}
+
+fn main() {
+    let mut args: Vec<String> = std::env::args().skip(1).collect();
+    let baseline = args.first().is_some_and(|a| a == "--baseline");
+    if baseline {
+        args.remove(0);
+    }
+    let vertices = arg(&args, 0, 200_000);
+    let edges = arg(&args, 1, 1_000_000);
+    let queries = arg(&args, 2, 100);
+
+    if baseline {
+        println!("baseline (Rc<RefCell> vertices)");
+        run(rc_graph::Graph::new(), vertices, edges, queries);
+    } else {
+        println!("interned ids");
+        run(Graph::new(), vertices, edges, queries);
+    }
+}
+
+fn run<G: Benched>(mut graph: G, vertices: usize, edges: usize, queries: usize) {
+    let mut rng = Lcg(42);
+
+    let before = resident_kb();
+    let start = Instant::now();
+    for v in 0..vertices {
+        graph.add_vertex(format!("v{}", v));
+    }
+    let mut added = 0;
+    while added < edges {
+        let a = format!("v{}", rng.below(vertices));
+        let b = format!("v{}", rng.below(vertices));
+        if graph.add_edge(&a, &b) {
+            added += 1;
+        }
+    }
+    let build = start.elapsed();
+    let after = resident_kb();
+
+    let start = Instant::now();
+    let mut found = 0;
+    let mut total_len = 0;
+    for _ in 0..queries {
+        let path = graph.find_path(format!("v{}", rng.below(vertices)), format!("v{}", rng.below(vertices)));
+        if !path.is_empty() {
+            found += 1;
+            total_len += path.len();
+        }
+    }
+    let search = start.elapsed();
+
+    println!("{} vertices, {} edges", graph.len(), added);
+    println!("build: {:.2?}", build);
+    if let (Some(before), Some(after)) = (before, after) {
+        println!("memory: {} MB", (after.saturating_sub(before)) / 1024);
+    }
+    println!("find_path: {:.2?} per query, {} of {} connected, {:.1} vertices per path",
+             search / queries.max(1) as u32, found, queries, total_len as f64 / found.max(1) as f64);
+}
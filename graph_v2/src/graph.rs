@@ -1,44 +1,74 @@
-#[doc="
-    Exposes only the Graph struct. Most of logic can be found in the Vertex struct. Most of the logic
-    is found in the Vertex Struct
+#![doc="
+    Exposes only the Graph struct, which is operated on through vertex keys.
 
     Comments:
-        - By not exposing Vertex, that means we have to operate through the use of keys. This causes
-        non-idomatic code. We chose this so that there would be no illegal mutation such as changing
-        keys by assignment, therefore ruining the HashMap
-        - Chose to do Rc and RefCell because we wanted to do some cool Drop behaviors, but it didn't
-        pan out, but it works fine.
-        - VertexCell struct made to wrap VertexCellT to implement Traits
+        - Keys are interned: each vertex gets the next id, an index into the key and adjacency
+        vectors, and the HashMap only turns keys into ids at the edges of the API. Searches work
+        on ids alone, so they hash nothing and allocate a few flat vectors.
+        - Edges are stored as arcs: sorted vectors of (target id, payload) per vertex. An
+        undirected edge is a pair of opposite arcs carrying the same payload, so directed and
+        undirected edges can be mixed in one graph.
+        - For in neighbours, each vertex also keeps the sources of the one-way arcs coming in.
+        The sources of two-way arcs are among its own targets already, so undirected graphs
+        don't pay for the in lists.
+        - The payload is generic; Graph on its own means edges carry nothing. Payloads that
+        convert to f64 are costs for the weighted searches, Dijkstra's algorithm and A*, which
+        share one implementation: Dijkstra is A* with a heuristic of zero.
+        - Vertices don't point at each other, so there are no reference cycles: dropping the graph
+        frees everything, and a Graph can be sent to another thread
+        - Removing a vertex moves the last vertex into its id, so ids stay dense and the vectors
+        can shrink; the moved vertex's neighbours are relabelled
+        - Since vertices are not exposed, keys can't be changed by assignment and ruin the HashMap
 
     Assumptions:
         - Vertex Keys are strings
+        - Simple graph: no self-loops, and at most one arc from a vertex to another
 "]
-use std::collections::{HashSet, HashMap, VecDeque};
-use std::cell::{RefCell};
-use std::rc::Rc;
-use std::hash::{Hash, Hasher};
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashSet, HashMap, VecDeque};
+use std::mem;
 
 
 /// Public container for creating graph structure that is mutable by reference
-/// A hashmap by String was chosen since vertex keys are strings.
-/// Side-effect is that once a graph instance goes out of scope, then all the nodes
-/// are out of scope as well, which is proper behavior.
-#[derive(Eq)]
-pub struct Graph {
-    vertices: HashMap<String, VertexCell>,
+/// Vertex ids index `keys`, `out` and `inc`; `ids` finds the id of a key.
+/// @param E - payload of each edge, e.g. a weight or a label
+pub struct Graph<E = ()> {
+    ids: HashMap<String, usize>,
+    keys: Vec<String>,
+    /// Arcs out of each vertex, (target id, payload), sorted by target
+    out: Vec<Vec<(usize, E)>>,
+    /// Sources of the arcs into each vertex with no arc back, sorted
+    inc: Vec<Vec<usize>>,
 }
 
-impl Graph {
-    pub fn new() -> Graph {
+impl<E> Default for Graph<E> {
+    fn default() -> Graph<E> {
         Graph {
-            vertices: HashMap::new(),
+            ids: HashMap::new(),
+            keys: Vec::new(),
+            out: Vec::new(),
+            inc: Vec::new(),
         }
     }
+}
 
+impl Graph {
+    /// Creates a graph whose edges carry no payload. Use Graph::default() for other payloads.
+    pub fn new() -> Graph {
+        Graph::default()
+    }
+}
+
+impl<E> Graph<E> {
     /// Adds a single vertex. Ensures only adding vertices with unique keys.
     /// @param key : String - string to be used as key in vertex
     pub fn add_vertex(&mut self, key : String) {
-        self.vertices.entry(key.clone()).or_insert(Vertex::new_cell(key));
+        if !self.ids.contains_key(&key) {
+            self.ids.insert(key.clone(), self.keys.len());
+            self.keys.push(key);
+            self.out.push(Vec::new());
+            self.inc.push(Vec::new());
+        }
     }
 
     /// Adds vertices. Ensures only adding vertices with unique keys.
@@ -49,257 +79,471 @@ impl Graph {
         }
     }
 
-    /// Adds vertices. Ensures only simple graphs i.e. no self-loopoing
-    /// @param a_key : String - key of node in graph
-    /// @param b_key : String - key of node in graph
+    /// Adds an edge from one vertex to another only. Ensures only simple graphs i.e. no self-loopoing
+    /// @param a_key : &str - key of the source node in graph
+    /// @param b_key : &str - key of the target node in graph
+    /// @param payload : E - weight or label of the edge
     ///
-    /// @return bool - returns whether
-    pub fn add_edge(&mut self, a_key : &String, b_key : &String) -> bool {
-        //prevents self-loop
-        if a_key != b_key {
-            if let Some(a) = self.vertices.get(a_key) {
-                if let Some(b) = self.vertices.get(b_key) {
-                    return Vertex::add_neighbor(a, b)
-                }
-            }
+    /// @return bool - whether a brand new edge was added; an existing one keeps its payload
+    pub fn add_directed_edge(&mut self, a_key : &str, b_key : &str, payload : E) -> bool {
+        match self.pair(a_key, b_key) {
+            Some((a, b)) => self.add_arc(a, b, payload),
+            None => false,
         }
-
-        false
     }
 
-    /// Returns number of nodes in graph. Uses len hashmap
-    /// @return usize -  number of nodes in graph
-    pub fn len(&self) -> usize {
-        self.vertices.len()
+    /// Ids of two distinct vertices in graph
+    fn pair(&self, a_key : &str, b_key : &str) -> Option<(usize, usize)> {
+        match (self.ids.get(a_key), self.ids.get(b_key)) {
+            (Some(&a), Some(&b)) if a != b => Some((a, b)),
+            _ => None,
+        }
     }
 
-    /// Returns keys of all neighbors of a node
-    /// @param vertex_key : String - key of node to find neighbors of
-    ///
-    /// @return HashSet<String> -
-    fn get_all_neighbors(&self, vertex_key: String) -> HashSet<String> {
-        let mut neighbors: HashSet<String> = HashSet::new();
-
-        if let Some(vc) = self.vertices.get(&vertex_key) {
-            let ref adj = vc.ptr.borrow().adj;
+    /// Records an arc from `a` to `b`, unless there is one
+    /// @return bool - whether it was added
+    fn add_arc(&mut self, a : usize, b : usize, payload : E) -> bool {
+        if !self.insert_arc(a, b, payload) {
+            return false;
+        }
+        if self.arc(b, a).is_ok() {
+            // the arc from b isn't one-way any more
+            remove_sorted(&mut self.inc[a], b);
+        } else {
+            insert_sorted(&mut self.inc[b], a);
+        }
+        true
+    }
 
-            for vc in adj {
-                neighbors.insert(vc.ptr.borrow().key.clone());
+    /// Adds an arc to `out` only, unless there is one
+    /// @return bool - whether it was added
+    fn insert_arc(&mut self, a : usize, b : usize, payload : E) -> bool {
+        match self.arc(a, b) {
+            Ok(_) => false,
+            Err(i) => {
+                self.out[a].insert(i, (b, payload));
+                true
             }
         }
+    }
 
-        neighbors
+    /// Position of the arc from `a` to `b` in `out[a]`, or where it would go
+    fn arc(&self, a : usize, b : usize) -> Result<usize, usize> {
+        self.out[a].binary_search_by_key(&b, |&(n, _)| n)
     }
 
-    // Returns a Vector of names of vertices in the path from source to destination
-    // Uses simple BFS-based alogorithm
-    /// @param src_key : String - key of node in graph
-    /// @param dst_key : String - key of node in graph
+    /// Removes a vertex and every edge to or from it
+    /// @param key : &str - key of node in graph
     ///
-    /// @return Vec<String>
-    pub fn find_path(&self, src_key: String, dst_key: String) -> Vec<String> {
-        let mut path: HashMap<String, String> = HashMap::new();
-        //stack for BFS
-        let mut queue: VecDeque<String> = VecDeque::new();
-        let mut return_path: Vec<String> = Vec::new();
-        let mut path_exists = false;
-
-        if self.vertices.contains_key(&src_key) && self.vertices.contains_key(&dst_key) {
-            let mut cur_node: String;
-            let mut visited: HashSet<String> = HashSet::new();;
-
-            queue.push_back(src_key.to_owned());
-            visited.insert(src_key.clone());
-
-            while queue.len() > 0 {
-                if let Some(cur_node) = queue.pop_front() {
-                    if cur_node == dst_key {
-                        path_exists = true;
-                        break;
-                    }
-                    let neighbors = self.get_all_neighbors(cur_node.clone());
-                    for n in neighbors {
-                        if !visited.contains(&n) {
-                            visited.insert(n.clone());
-                            queue.push_back(n.clone());
-                            path.insert(n.clone(), cur_node.clone());
-                        }
-                    }
+    /// @return bool - whether there was such a node
+    pub fn remove_vertex(&mut self, key : &str) -> bool {
+        let v = match self.ids.remove(key) {
+            Some(v) => v,
+            None => return false,
+        };
+
+        // Nothing may refer to v once it's gone
+        for (n, _) in mem::take(&mut self.out[v]) {
+            match self.arc(n, v) {
+                Ok(i) => {
+                    self.out[n].remove(i);
+                    release(&mut self.out[n]);
                 }
+                Err(_) => remove_sorted(&mut self.inc[n], v),
             }
+        }
+        for s in mem::take(&mut self.inc[v]) {
+            let i = self.arc(s, v).expect("inc lists sources of arcs");
+            self.out[s].remove(i);
+            release(&mut self.out[s]);
+        }
 
-            // Going through the path HashMap to form the vector of Strings
-            cur_node = dst_key.clone();
-            if path_exists {
-                while cur_node != src_key {
-                    return_path.push(cur_node.to_owned());
-                    if let Some(v) = path.get(&cur_node) {
-                        cur_node = v.to_owned();
-                    }
+        // The last vertex takes v's id, so ids stay dense
+        let last = self.keys.len() - 1;
+        self.keys.swap_remove(v);
+        self.out.swap_remove(v);
+        self.inc.swap_remove(v);
+        if v != last {
+            *self.ids.get_mut(&self.keys[v]).expect("every key has an id") = v;
+            let targets : Vec<usize> = self.out[v].iter().map(|&(n, _)| n).collect();
+            for n in targets {
+                if self.arc(n, last).is_ok() {
+                    self.relabel_arc(n, last, v);
+                } else {
+                    remove_sorted(&mut self.inc[n], last);
+                    insert_sorted(&mut self.inc[n], v);
                 }
-                return_path.push(cur_node.to_owned());
+            }
+            for s in self.inc[v].clone() {
+                self.relabel_arc(s, last, v);
             }
         }
-
-        if return_path.len() == 1 {
-            return Vec::new();
-        } else {
-            return_path.reverse();
-            return return_path;
+        release(&mut self.keys);
+        release(&mut self.out);
+        release(&mut self.inc);
+        if self.ids.len() < self.ids.capacity() / 4 {
+            self.ids.shrink_to_fit();
         }
+        true
     }
-}
-
 
-impl PartialEq for Graph {
-    fn eq(&self, other: &Graph) -> bool {
-        (self.vertices == other.vertices)
+    /// Makes the arc from `a` to `old` lead to `new` instead
+    fn relabel_arc(&mut self, a : usize, old : usize, new : usize) {
+        let i = self.arc(a, old).expect("relabelled arcs exist");
+        let (_, payload) = self.out[a].remove(i);
+        let j = self.arc(a, new).unwrap_err();
+        self.out[a].insert(j, (new, payload));
     }
 
-    fn ne(&self, other: &Graph) -> bool {
-        !(self.eq(other))
+    /// Removes the edge between two nodes, in both directions
+    /// @param a_key : &str - key of node in graph
+    /// @param b_key : &str - key of node in graph
+    ///
+    /// @return bool - whether there was an edge in either direction
+    pub fn remove_edge(&mut self, a_key : &str, b_key : &str) -> bool {
+        let (a, b) = match self.pair(a_key, b_key) {
+            Some(pair) => pair,
+            None => return false,
+        };
+        match (self.arc(a, b), self.arc(b, a)) {
+            (Ok(i), Ok(j)) => {
+                // two-way arcs are in neither inc list
+                self.out[a].remove(i);
+                self.out[b].remove(j);
+                release(&mut self.out[a]);
+                release(&mut self.out[b]);
+                true
+            }
+            (Ok(_), Err(_)) => self.remove_arc(a, b),
+            (Err(_), Ok(_)) => self.remove_arc(b, a),
+            (Err(_), Err(_)) => false,
+        }
     }
-}
-
-
 
+    /// Removes the edge from one node to another, leaving any edge back
+    /// @param a_key : &str - key of the source node in graph
+    /// @param b_key : &str - key of the target node in graph
+    ///
+    /// @return bool - whether there was such an edge
+    pub fn remove_directed_edge(&mut self, a_key : &str, b_key : &str) -> bool {
+        match self.pair(a_key, b_key) {
+            Some((a, b)) => self.remove_arc(a, b),
+            None => false,
+        }
+    }
 
-/// See comments for choice in using this type
-type VertexCellT = Rc<RefCell<Vertex>>;
-
+    /// Removes the arc from `a` to `b`, if there is one
+    /// @return bool - whether it was removed
+    fn remove_arc(&mut self, a : usize, b : usize) -> bool {
+        let i = match self.arc(a, b) {
+            Ok(i) => i,
+            Err(_) => return false,
+        };
+        self.out[a].remove(i);
+        release(&mut self.out[a]);
+        if self.arc(b, a).is_ok() {
+            // the arc from b is one-way now
+            insert_sorted(&mut self.inc[a], b);
+        } else {
+            remove_sorted(&mut self.inc[b], a);
+        }
+        true
+    }
 
-#[derive(Eq)]
-struct Vertex {
-    adj: HashSet<VertexCell>,
-    key: String,
-}
+    /// Returns number of nodes in graph
+    /// @return usize -  number of nodes in graph
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
 
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
 
-#[derive(Eq)]
-struct VertexCell{
-    ptr : VertexCellT
-}
+    /// Payload of the edge from one node to another
+    /// @param a_key : &str - key of the source node
+    /// @param b_key : &str - key of the target node
+    ///
+    /// @return Option<&E> - None if there is no such edge
+    pub fn edge_weight(&self, a_key : &str, b_key : &str) -> Option<&E> {
+        let (a, b) = self.pair(a_key, b_key)?;
+        let i = self.arc(a, b).ok()?;
+        Some(&self.out[a][i].1)
+    }
 
+    /// Returns keys of all neighbors of a node, the ones its edges lead to
+    /// @param vertex_key : String - key of node to find neighbors of
+    ///
+    /// @return HashSet<String> - empty if there is no such node
+    pub fn get_all_neighbors(&self, vertex_key: String) -> HashSet<String> {
+        self.out_neighbors(&vertex_key).into_iter().map(|key| key.to_string()).collect()
+    }
 
-/// Following Traits implemented to use with HashSet
-impl Clone for VertexCell {
-    fn clone(&self) -> Self {
-        VertexCell {
-            ptr : self.ptr.clone()
+    /// Keys of the nodes a node has an edge to, in the order they were added
+    /// to the graph, except that removing a node puts the last one added in its place
+    /// @param vertex_key : &str - key of node in graph
+    ///
+    /// @return Vec<&str> - empty if there is no such node
+    pub fn out_neighbors(&self, vertex_key : &str) -> Vec<&str> {
+        match self.ids.get(vertex_key) {
+            Some(&id) => self.out[id].iter().map(|&(n, _)| self.keys[n].as_str()).collect(),
+            None => Vec::new(),
         }
     }
 
-    fn clone_from(&mut self, source : &Self) {
-        self.ptr = source.ptr.clone();
+    /// Keys of the nodes with an edge to a node, in the order they were added
+    /// to the graph, except that removing a node puts the last one added in its place
+    /// @param vertex_key : &str - key of node in graph
+    ///
+    /// @return Vec<&str> - empty if there is no such node
+    pub fn in_neighbors(&self, vertex_key : &str) -> Vec<&str> {
+        let id = match self.ids.get(vertex_key) {
+            Some(&id) => id,
+            None => return Vec::new(),
+        };
+        let mut sources : Vec<usize> = self.out[id].iter()
+            .map(|&(n, _)| n)
+            .filter(|&n| self.arc(n, id).is_ok())
+            .chain(self.inc[id].iter().cloned())
+            .collect();
+        sources.sort();
+        sources.into_iter().map(|n| self.keys[n].as_str()).collect()
     }
-}
 
-impl PartialEq for VertexCell {
-    fn eq(&self, other: &VertexCell) -> bool {
-        (self.ptr.borrow().key == other.ptr.borrow().key)
+    /// Returns a Vector of names of vertices in the path from source to destination, following
+    /// edges in their direction
+    /// Uses simple BFS-based alogorithm
+    /// @param src_key : String - key of node in graph
+    /// @param dst_key : String - key of node in graph
+    ///
+    /// @return Vec<String> - empty if there is no path, or the path would be a single node
+    pub fn find_path(&self, src_key: String, dst_key: String) -> Vec<String> {
+        let (src, dst) = match self.pair(&src_key, &dst_key) {
+            Some(pair) => pair,
+            None => return Vec::new(),
+        };
+
+        match self.bfs(src, dst) {
+            Some(path) => path.into_iter().map(|id| self.keys[id].clone()).collect(),
+            None => Vec::new(),
+        }
     }
 
-    fn ne(&self, other: &VertexCell) -> bool {
-        !self.eq(other)
-    }
-}
+    /// Breadth-first search by vertex id
+    /// @return Option<Vec<usize>> - ids from src to dst, both included
+    fn bfs(&self, src : usize, dst : usize) -> Option<Vec<usize>> {
+        // vertex the search reached each vertex from; src is its own parent
+        let mut parent : Vec<Option<usize>> = vec![None; self.len()];
+        let mut queue : VecDeque<usize> = VecDeque::new();
+        parent[src] = Some(src);
+        queue.push_back(src);
+
+        while let Some(cur) = queue.pop_front() {
+            if cur == dst {
+                return Some(trace(&parent, src, dst));
+            }
+            for &(n, _) in &self.out[cur] {
+                if parent[n].is_none() {
+                    parent[n] = Some(cur);
+                    queue.push_back(n);
+                }
+            }
+        }
 
-impl Hash for VertexCell {
-    fn hash<H :Hasher>(&self, state : &mut H) {
-        let v = self.ptr.borrow();
-        v.hash(state);
+        None
     }
 }
 
-impl Vertex {
-    /// Creates a single vertex.
-    /// @param key : String - string to be used as key in vertex
+impl<E: Copy + Into<f64>> Graph<E> {
+    /// Cheapest path from source to destination, following edges in their direction, by
+    /// Dijkstra's algorithm. Edge payloads are the costs and can't be negative.
+    /// @param src_key : &str - key of node in graph
+    /// @param dst_key : &str - key of node in graph
     ///
-    /// @return Vertex
-    pub fn new(key: String) -> Vertex {
-        Vertex {
-            adj: HashSet::new(),
-            key: key,
-        }
+    /// @return Option<(Vec<String>, f64)> - keys along the path, both ends included, and its
+    /// total cost; None if either node is missing or there is no path
+    pub fn shortest_path_weighted(&self, src_key : &str, dst_key : &str) -> Option<(Vec<String>, f64)> {
+        self.shortest_path_astar(src_key, dst_key, |_| 0.0)
     }
 
-    /// Helper to create a single vertexcell.
-    /// @param key : String - string to be used as key in vertex
+    /// Cheapest path like `shortest_path_weighted`, by A*: the heuristic estimates the cost from
+    /// a node to the destination, and nodes that look closer are searched first. The path is the
+    /// cheapest as long as the heuristic never overestimates.
+    /// @param src_key : &str - key of node in graph
+    /// @param dst_key : &str - key of node in graph
+    /// @param heuristic : H - estimated cost from the node with the given key to dst_key
     ///
-    /// @return VertexCell
-    pub fn new_cell(key: String) -> VertexCell {
-        VertexCell {
-            ptr : Rc::new(RefCell::new(Vertex::new(key)))
+    /// @return Option<(Vec<String>, f64)> - keys along the path and its total cost
+    pub fn shortest_path_astar<H>(&self, src_key : &str, dst_key : &str, heuristic : H) -> Option<(Vec<String>, f64)>
+        where H: Fn(&str) -> f64
+    {
+        let src = *self.ids.get(src_key)?;
+        let dst = *self.ids.get(dst_key)?;
+        let (path, cost) = self.cheapest(src, dst, |id| heuristic(&self.keys[id]))?;
+        Some((path.into_iter().map(|id| self.keys[id].clone()).collect(), cost))
+    }
+
+    /// A* search by vertex id, with a binary heap of the vertices to expand
+    /// @return Option<(Vec<usize>, f64)> - ids from src to dst, both included, and the cost
+    fn cheapest<H: Fn(usize) -> f64>(&self, src : usize, dst : usize, heuristic : H) -> Option<(Vec<usize>, f64)> {
+        let mut cost : Vec<f64> = vec![f64::INFINITY; self.len()];
+        let mut parent : Vec<Option<usize>> = vec![None; self.len()];
+        let mut heap : BinaryHeap<Pending> = BinaryHeap::new();
+        cost[src] = 0.0;
+        parent[src] = Some(src);
+        heap.push(Pending { priority: heuristic(src), cost: 0.0, vertex: src });
+
+        while let Some(Pending { cost: cur_cost, vertex: cur, .. }) = heap.pop() {
+            if cur == dst {
+                return Some((trace(&parent, src, dst), cur_cost));
+            }
+            // a cheaper way here was found after this entry was pushed
+            if cur_cost > cost[cur] {
+                continue;
+            }
+            for &(n, payload) in &self.out[cur] {
+                let weight : f64 = payload.into();
+                assert!(weight >= 0.0, "edge costs can't be negative, {} -> {} costs {}",
+                        self.keys[cur], self.keys[n], weight);
+                let next = cur_cost + weight;
+                if next < cost[n] {
+                    cost[n] = next;
+                    parent[n] = Some(cur);
+                    heap.push(Pending { priority: next + heuristic(n), cost: next, vertex: n });
+                }
+            }
         }
+
+        None
     }
+}
 
-    /// Makes two vertices neighbors. Ensures undirected edge
-    /// @param a : &VertexCell
-    /// @param b : &VertexCell
+impl<E: Clone> Graph<E> {
+    /// Adds an undirected edge carrying a payload. Ensures only simple graphs i.e. no self-loopoing
+    /// @param a_key : &str - key of node in graph
+    /// @param b_key : &str - key of node in graph
+    /// @param payload : E - weight or label of the edge
     ///
-    /// @return bool - whether a brand new edge was added
-    pub fn add_neighbor(a: &VertexCell, b : &VertexCell) -> bool {
-        let i = Vertex::add_link(a, b);
-        Vertex::add_link(b, a) && i
+    /// @return bool - whether an edge was added in either direction; existing ones keep their payload
+    pub fn add_weighted_edge(&mut self, a_key : &str, b_key : &str, payload : E) -> bool {
+        match self.pair(a_key, b_key) {
+            Some((a, b)) => {
+                let had_forward = self.arc(a, b).is_ok();
+                let had_backward = self.arc(b, a).is_ok();
+                // a new pair of arcs never goes through inc, which would keep the capacity
+                match (had_forward, had_backward) {
+                    (false, false) => {
+                        self.insert_arc(a, b, payload.clone());
+                        self.insert_arc(b, a, payload)
+                    }
+                    (false, true) => self.add_arc(a, b, payload),
+                    (true, false) => self.add_arc(b, a, payload),
+                    (true, true) => false,
+                }
+            }
+            None => false,
+        }
     }
+}
 
-    /// Creates a directed edge between vertices
-    /// @param a : &VertexCell
-    /// @param b : &VertexCell
+impl<E: Clone + Default> Graph<E> {
+    /// Adds an undirected edge with the default payload. Ensures only simple graphs i.e. no self-loopoing
+    /// @param a_key : &str - key of node in graph
+    /// @param b_key : &str - key of node in graph
     ///
-    /// @return bool - whether a brand new edge was added
-    fn add_link(a : &VertexCell, b : &VertexCell) -> bool{
-        let mut mut_ref = a.ptr.borrow_mut();
-        mut_ref.adj.insert(b.clone())
+    /// @return bool - whether an edge was added in either direction
+    pub fn add_edge(&mut self, a_key : &str, b_key : &str) -> bool {
+        self.add_weighted_edge(a_key, b_key, E::default())
     }
+}
 
 
-    /// Checks whether two vertices are neighbors
-    /// @param a : &VertexCell
-    /// @param b : &VertexCell
-    ///
-    /// @return bool - whether a brand new edge was added
-    pub fn are_neighbors(a: &VertexCell, b : &VertexCell) -> bool {
-        a.ptr.borrow().adj.contains(b)
+fn insert_sorted(ids : &mut Vec<usize>, id : usize) {
+    if let Err(i) = ids.binary_search(&id) {
+        ids.insert(i, id);
+    }
+}
+
+fn remove_sorted(ids : &mut Vec<usize>, id : usize) {
+    if let Ok(i) = ids.binary_search(&id) {
+        ids.remove(i);
+        release(ids);
     }
+}
 
-    pub fn get_name(self) -> String {
-        return self.key;
+/// Gives memory back once a vector uses less than a quarter of it, so removals free memory
+/// without reallocating on every one
+fn release<T>(v : &mut Vec<T>) {
+    if v.len() < v.capacity() / 4 {
+        v.shrink_to_fit();
     }
 }
 
 
-/// Following Traits implemented to use with HashSet
-impl Clone for Vertex {
-    fn clone(&self) -> Self {
-        Vertex {
-            key : self.key.clone(),
-            adj : self.adj.clone(),
-        }
+/// Path from src to dst, following the vertex each vertex was reached from back to src
+/// @param parent : &[Option<usize>] - where the search reached each vertex from; src is its own
+fn trace(parent : &[Option<usize>], src : usize, dst : usize) -> Vec<usize> {
+    let mut path = vec![dst];
+    let mut node = dst;
+    while node != src {
+        node = parent[node].expect("visited vertices have a parent");
+        path.push(node);
     }
+    path.reverse();
+    path
+}
+
+
+/// Vertex waiting in the heap of a weighted search. Ordered so the lowest priority, the cost so
+/// far plus the heuristic's estimate of the rest, comes out of the max-heap first.
+struct Pending {
+    priority : f64,
+    cost : f64,
+    vertex : usize,
+}
 
-    fn clone_from(&mut self, source : &Self) {
-        self.key = source.key.clone();
-        self.adj = source.adj.clone();
+impl PartialEq for Pending {
+    fn eq(&self, other: &Pending) -> bool {
+        self.cmp(other) == Ordering::Equal
     }
 }
 
-impl PartialEq for Vertex {
-    fn eq(&self, other: &Vertex) -> bool {
-        (self.key == other.key)
+impl Eq for Pending {}
+
+impl PartialOrd for Pending {
+    fn partial_cmp(&self, other: &Pending) -> Option<Ordering> {
+        Some(self.cmp(other))
     }
+}
 
-    fn ne(&self, other: &Vertex) -> bool {
-        (self.key != other.key)
+impl Ord for Pending {
+    fn cmp(&self, other: &Pending) -> Ordering {
+        other.priority.partial_cmp(&self.priority).unwrap_or(Ordering::Equal)
+            .then_with(|| other.vertex.cmp(&self.vertex))
     }
 }
 
-impl Hash for Vertex {
-    fn hash<H: Hasher>(&self, state: &mut H) {
-        self.key.hash(state);
+
+/// Graphs are equal when they have the same keys and the same edges and payloads between them,
+/// whatever order they were added in
+impl<E: PartialEq> PartialEq for Graph<E> {
+    fn eq(&self, other: &Graph<E>) -> bool {
+        self.len() == other.len() && self.keys.iter().enumerate().all(|(id, key)| {
+            match other.ids.get(key) {
+                Some(&other_id) => {
+                    self.out[id].len() == other.out[other_id].len() &&
+                        self.out[id].iter().all(|&(n, ref payload)| {
+                            other.edge_weight(key, &self.keys[n]) == Some(payload)
+                        })
+                }
+                None => false,
+            }
+        })
     }
 }
 
+impl<E: Eq> Eq for Graph<E> {}
+
 
 /*********************** TESTS **************************************************/
 
@@ -339,7 +583,7 @@ mod graph_tests {
     fn graph_test_get_neighbors() {
         let mut g = Graph::new();
         g.add_vertices(vec!["a".to_string(), "b".to_string(), 'c'.to_string()].to_owned());
-        g.add_edge(&"a".to_string(), &"b".to_string());
+        g.add_edge("a", "b");
         let results : HashSet<String> = g.get_all_neighbors("a".to_string());
 
         let mut expected : HashSet<String> = HashSet::new();
@@ -353,8 +597,8 @@ mod graph_tests {
     fn graph_test_get_neighbors_more() {
         let mut g = Graph::new();
         g.add_vertices(vec!["a".to_string(), "b".to_string(), 'c'.to_string()].to_owned());
-        g.add_edge(&"a".to_string(), &"b".to_string());
-        g.add_edge(&"a".to_string(), &"c".to_string());
+        g.add_edge("a", "b");
+        g.add_edge("a", "c");
         let results : HashSet<String> = g.get_all_neighbors("a".to_string());
 
         let mut expected : HashSet<String> = HashSet::new();
@@ -372,9 +616,9 @@ mod graph_tests {
         g.add_vertices(vec!["a".to_string(), "b".to_string()].to_owned());
         assert_eq!(g.len(), 2);
 
-        g.add_edge(&"a".to_string(), &"b".to_string());
+        g.add_edge("a", "b");
         let path = g.find_path("a".to_string(), "b".to_string());
-        assert!(path.len() > 0);
+        assert!(!path.is_empty());
     }
 
     #[test]
@@ -385,9 +629,9 @@ mod graph_tests {
         "d".to_string()].to_owned());
 
         assert_eq!(g.len(), 4);
-        g.add_edge(&"a".to_string(), &"b".to_string());
-        g.add_edge(&"c".to_string(), &"d".to_string());
-        g.add_edge(&"a".to_string(), &"d".to_string());
+        g.add_edge("a", "b");
+        g.add_edge("c", "d");
+        g.add_edge("a", "d");
         let path = g.find_path("c".to_string(), "b".to_string());
         assert_eq!(path.len(), 4);
     }
@@ -401,51 +645,410 @@ mod graph_tests {
         "d".to_string()].to_owned());
 
         assert_eq!(g.len(), 4);
-        g.add_edge(&"a".to_string(), &"b".to_string());
-        g.add_edge(&"c".to_string(), &"d".to_string());
+        g.add_edge("a", "b");
+        g.add_edge("c", "d");
         let path = g.find_path("c".to_string(), "b".to_string());
         assert_eq!(path.len(),0);
     }
+
+    #[test]
+    fn graph_test_equal_regardless_of_order() {
+        let mut g1 = Graph::new();
+        g1.add_vertices(vec!["a".to_string(), "b".to_string(), "c".to_string()]);
+        g1.add_edge("a", "b");
+        g1.add_edge("b", "c");
+
+        let mut g2 = Graph::new();
+        g2.add_vertices(vec!["c".to_string(), "b".to_string(), "a".to_string()]);
+        g2.add_edge("c", "b");
+        assert!(g1 != g2);
+        g2.add_edge("b", "a");
+        assert!(g1 == g2);
+    }
+
+    #[test]
+    fn graph_test_directed_edges() {
+        let mut g : Graph<f64> = Graph::default();
+        g.add_vertices(vec!["a".to_string(), "b".to_string(), "c".to_string()]);
+
+        assert!(g.add_directed_edge("a", "b", 1.5));
+        assert!(!g.add_directed_edge("a", "b", 2.0));
+        assert!(g.add_directed_edge("b", "c", 1.0));
+        assert!(!g.add_directed_edge("c", "c", 1.0));
+        assert!(!g.add_directed_edge("c", "x", 1.0));
+
+        assert_eq!(g.out_neighbors("a"), vec!["b"]);
+        assert!(g.out_neighbors("c").is_empty());
+        assert_eq!(g.in_neighbors("c"), vec!["b"]);
+        assert!(g.in_neighbors("a").is_empty());
+        assert!(g.in_neighbors("x").is_empty());
+
+        assert_eq!(g.find_path("a".to_string(), "c".to_string()), vec!["a", "b", "c"]);
+        assert!(g.find_path("c".to_string(), "a".to_string()).is_empty());
+    }
+
+    #[test]
+    fn graph_test_edge_weight() {
+        let mut g : Graph<&str> = Graph::default();
+        g.add_vertices(vec!["a".to_string(), "b".to_string(), "c".to_string()]);
+        g.add_weighted_edge("a", "b", "road");
+        g.add_directed_edge("b", "c", "river");
+
+        assert_eq!(g.edge_weight("a", "b"), Some(&"road"));
+        assert_eq!(g.edge_weight("b", "a"), Some(&"road"));
+        assert_eq!(g.edge_weight("b", "c"), Some(&"river"));
+        assert_eq!(g.edge_weight("c", "b"), None);
+        assert_eq!(g.edge_weight("a", "c"), None);
+
+        // completing the reverse arc keeps the existing payload
+        assert!(g.add_weighted_edge("c", "b", "bridge"));
+        assert!(!g.add_weighted_edge("c", "b", "ford"));
+        assert_eq!(g.edge_weight("b", "c"), Some(&"river"));
+        assert_eq!(g.edge_weight("c", "b"), Some(&"bridge"));
+        assert_eq!(g.get_all_neighbors("b".to_string()).len(), 2);
+    }
+
+    #[test]
+    fn graph_test_equal_compares_payloads() {
+        let mut g1 : Graph<u32> = Graph::default();
+        let mut g2 : Graph<u32> = Graph::default();
+        for g in [&mut g1, &mut g2] {
+            g.add_vertices(vec!["a".to_string(), "b".to_string()]);
+        }
+        g1.add_directed_edge("a", "b", 1);
+        g2.add_directed_edge("b", "a", 1);
+        assert!(g1 != g2);
+
+        g2.add_directed_edge("a", "b", 2);
+        g1.add_directed_edge("b", "a", 1);
+        assert!(g1 != g2);
+    }
+
+    fn weighted(edges : &[(&str, &str, f64)]) -> Graph<f64> {
+        let mut g : Graph<f64> = Graph::default();
+        for &(a, b, cost) in edges {
+            g.add_vertex(a.to_string());
+            g.add_vertex(b.to_string());
+            g.add_weighted_edge(a, b, cost);
+        }
+        g
+    }
+
+    #[test]
+    fn graph_test_dijkstra_prefers_cheaper_path() {
+        let mut g = weighted(&[("a", "b", 1.0), ("b", "c", 1.0), ("c", "d", 1.0), ("a", "d", 5.0)]);
+
+        assert_eq!(g.find_path("a".to_string(), "d".to_string()), vec!["a", "d"]);
+        assert_eq!(g.shortest_path_weighted("a", "d"),
+                   Some((vec!["a".to_string(), "b".to_string(), "c".to_string(), "d".to_string()], 3.0)));
+        assert_eq!(g.shortest_path_weighted("d", "a").map(|(_, cost)| cost), Some(3.0));
+        assert_eq!(g.shortest_path_weighted("b", "b"), Some((vec!["b".to_string()], 0.0)));
+
+        g.add_vertex("e".to_string());
+        g.add_directed_edge("d", "e", 0.5);
+        assert_eq!(g.shortest_path_weighted("a", "e").map(|(_, cost)| cost), Some(3.5));
+        assert_eq!(g.shortest_path_weighted("e", "a"), None);
+        assert_eq!(g.shortest_path_weighted("a", "x"), None);
+    }
+
+    #[test]
+    fn graph_test_dijkstra_integer_costs() {
+        let mut g : Graph<u32> = Graph::default();
+        g.add_vertices(vec!["a".to_string(), "b".to_string(), "c".to_string()]);
+        g.add_directed_edge("a", "b", 7);
+        g.add_directed_edge("a", "c", 2);
+        g.add_directed_edge("c", "b", 3);
+
+        assert_eq!(g.shortest_path_weighted("a", "b"),
+                   Some((vec!["a".to_string(), "c".to_string(), "b".to_string()], 5.0)));
+    }
+
+    #[test]
+    #[should_panic(expected = "edge costs can't be negative")]
+    fn graph_test_dijkstra_negative_cost() {
+        let g = weighted(&[("a", "b", -1.0)]);
+        g.shortest_path_weighted("a", "b");
+    }
+
+    #[test]
+    fn graph_test_astar_on_grid() {
+        // 5x5 grid, moving right costs 1 and moving down costs 2, with a wall in column 2
+        // open only at the bottom
+        let mut g : Graph<f64> = Graph::default();
+        let key = |x : i32, y : i32| format!("{},{}", x, y);
+        for y in 0..5 {
+            for x in 0..5 {
+                g.add_vertex(key(x, y));
+            }
+        }
+        for y in 0..5 {
+            for x in 0..5 {
+                if x < 4 && (x != 1 && x != 2 || y == 4) {
+                    g.add_weighted_edge(&key(x, y), &key(x + 1, y), 1.0);
+                }
+                if y < 4 {
+                    g.add_weighted_edge(&key(x, y), &key(x, y + 1), 2.0);
+                }
+            }
+        }
+        let manhattan = |k : &str| {
+            let xy : Vec<f64> = k.split(',').map(|n| n.parse().unwrap()).collect();
+            (4.0 - xy[0]) + 2.0 * xy[1]
+        };
+
+        let dijkstra = g.shortest_path_weighted("0,0", "4,0").unwrap();
+        let astar = g.shortest_path_astar("0,0", "4,0", manhattan).unwrap();
+        assert_eq!(dijkstra.1, 4.0 + 2.0 * 8.0);
+        assert_eq!(astar.1, dijkstra.1);
+        assert_eq!(astar.0.first().map(|k| k.as_str()), Some("0,0"));
+        assert_eq!(astar.0.last().map(|k| k.as_str()), Some("4,0"));
+        assert!(astar.0.contains(&"2,4".to_string()));
+    }
+
+    fn neighbors(g : &Graph<f64>, key : &str) -> HashSet<String> {
+        g.get_all_neighbors(key.to_string())
+    }
+
+    fn set(keys : &[&str]) -> HashSet<String> {
+        keys.iter().map(|k| k.to_string()).collect()
+    }
+
+    #[test]
+    fn graph_test_remove_edge() {
+        let mut g = weighted(&[("a", "b", 1.0), ("b", "c", 2.0), ("a", "c", 4.0)]);
+
+        assert!(g.remove_edge("b", "a"));
+        assert!(!g.remove_edge("a", "b"));
+        assert!(!g.remove_edge("a", "x"));
+        assert!(!g.remove_edge("a", "a"));
+
+        assert_eq!(g.len(), 3);
+        assert_eq!(neighbors(&g, "a"), set(&["c"]));
+        assert_eq!(neighbors(&g, "b"), set(&["c"]));
+        assert_eq!(g.edge_weight("a", "b"), None);
+        assert_eq!(g.shortest_path_weighted("a", "b").map(|(_, cost)| cost), Some(6.0));
+    }
+
+    #[test]
+    fn graph_test_remove_directed_edge() {
+        let mut g = weighted(&[("a", "b", 1.0)]);
+        g.add_vertex("c".to_string());
+        g.add_directed_edge("c", "a", 1.0);
+
+        assert!(g.remove_directed_edge("a", "b"));
+        assert!(!g.remove_directed_edge("a", "b"));
+        assert!(g.out_neighbors("a").is_empty());
+        assert_eq!(g.in_neighbors("a"), vec!["b", "c"]);
+        assert_eq!(g.out_neighbors("b"), vec!["a"]);
+
+        // removing an edge takes it out in whichever direction it goes
+        assert!(g.remove_edge("a", "c"));
+        assert!(g.remove_edge("a", "b"));
+        assert!(g.in_neighbors("a").is_empty());
+        assert!(g.out_neighbors("b").is_empty() && g.out_neighbors("c").is_empty());
+    }
+
+    #[test]
+    fn graph_test_remove_vertex() {
+        let mut g = weighted(&[("a", "b", 1.0), ("b", "c", 2.0), ("c", "d", 3.0), ("d", "a", 4.0)]);
+        g.add_directed_edge("b", "d", 5.0);
+
+        assert!(g.remove_vertex("a"));
+        assert!(!g.remove_vertex("a"));
+        assert!(!g.remove_vertex("x"));
+
+        assert_eq!(g.len(), 3);
+        assert_eq!(neighbors(&g, "b"), set(&["c", "d"]));
+        assert_eq!(neighbors(&g, "d"), set(&["c"]));
+        assert_eq!(g.in_neighbors("d"), vec!["b", "c"]);
+        assert_eq!(g.edge_weight("d", "c"), Some(&3.0));
+        assert_eq!(g.edge_weight("b", "d"), Some(&5.0));
+        assert!(g.find_path("a".to_string(), "b".to_string()).is_empty());
+
+        // the key can be used again, for a vertex without edges
+        g.add_vertex("a".to_string());
+        assert_eq!(g.len(), 4);
+        assert!(neighbors(&g, "a").is_empty());
+        assert!(g.in_neighbors("a").is_empty());
+    }
+
+    #[test]
+    fn graph_test_remove_every_vertex() {
+        let mut g = weighted(&[("a", "b", 1.0), ("b", "c", 1.0), ("c", "a", 1.0)]);
+
+        for key in &["b", "a", "c"] {
+            assert!(g.remove_vertex(key));
+        }
+
+        assert!(g.is_empty());
+        assert!(g == Graph::default());
+    }
+
+    #[test]
+    fn graph_test_send() {
+        fn assert_send<T: Send>(_: &T) {}
+        assert_send(&Graph::new());
+    }
 }
 
 #[cfg(test)]
 mod vertex_tests {
-    use super::{Vertex};
+    use super::{Graph};
+    use std::collections::{HashSet};
+
+    fn graph(keys: &[&str]) -> Graph {
+        let mut g = Graph::new();
+        g.add_vertices(keys.iter().map(|k| k.to_string()).collect());
+        g
+    }
 
     #[test]
     fn two_nodes() {
-        let cell1 = Vertex::new_cell("one".to_string());
-        let cell2 = Vertex::new_cell("two".to_string());
+        let mut g = graph(&["one", "two"]);
 
-        Vertex::add_neighbor(&cell1, &cell2);
+        assert!(g.add_edge("one", "two"));
 
-        assert!(cell1.ptr.borrow().adj.len() == cell2.ptr.borrow().adj.len() && cell1.ptr.borrow().adj.len() == 1);
+        assert!(g.out[0] == vec![(1, ())] && g.out[1] == vec![(0, ())]);
+        assert!(g.inc[0].is_empty() && g.inc[1].is_empty());
     }
 
     #[test]
     fn three_nodes() {
-        let cell1 = Vertex::new_cell("one".to_string());
-        let cell2 = Vertex::new_cell("two".to_string());
-        let cell3 = Vertex::new_cell("three".to_string());
+        let mut g = graph(&["one", "two", "three"]);
+
+        g.add_edge("one", "two");
+        g.add_edge("one", "three");
+        assert!(!g.add_edge("three", "one"));
+        assert!(!g.add_edge("two", "two"));
+
+        assert_eq!(g.out[0], vec![(1, ()), (2, ())]);
+        assert_eq!(g.out[1], vec![(0, ())]);
+        assert!(g.inc.iter().all(|sources| sources.is_empty()));
+    }
 
-        Vertex::add_neighbor(&cell1, &cell2);
-        Vertex::add_neighbor(&cell1, &cell3);
+    #[test]
+    fn directed_arcs() {
+        let mut g : Graph<u32> = Graph::default();
+        g.add_vertices(vec!["one".to_string(), "two".to_string(), "three".to_string()]);
+
+        assert!(g.add_directed_edge("two", "one", 4));
+        assert!(g.add_directed_edge("three", "one", 5));
+        assert!(g.add_weighted_edge("one", "two", 6));
+
+        assert_eq!(g.out[0], vec![(1, 6)]);
+        assert_eq!(g.out[1], vec![(0, 4)]);
+        assert_eq!(g.inc[0], vec![2]);
+        assert!(g.inc[1].is_empty());
+        assert_eq!(g.in_neighbors("one"), vec!["two", "three"]);
+    }
 
-        let mut b : bool = cell1.ptr.borrow().adj.len() == 2;
-        b = b && (cell2.ptr.borrow().adj.len() == 1);
-        b = b && (cell3.ptr.borrow().adj.len() == 1);
+    /// Checks the invariants of the storage against the arcs the graph should have
+    fn assert_consistent(g : &Graph<u32>, arcs : &HashSet<(usize, usize)>, keys : &[String]) {
+        assert_eq!(g.len(), g.out.len());
+        assert_eq!(g.len(), g.inc.len());
+        assert_eq!(g.len(), g.ids.len());
+        for (id, key) in g.keys.iter().enumerate() {
+            assert_eq!(g.ids[key], id);
+            assert!(g.out[id].windows(2).all(|w| w[0].0 < w[1].0));
+            assert!(g.inc[id].windows(2).all(|w| w[0] < w[1]));
+            for &s in &g.inc[id] {
+                assert!(g.arc(s, id).is_ok() && g.arc(id, s).is_err());
+            }
+        }
+        let mut stored : HashSet<(usize, usize)> = HashSet::new();
+        for (id, out) in g.out.iter().enumerate() {
+            for &(n, payload) in out {
+                assert!(g.inc[n].contains(&id) != g.arc(n, id).is_ok());
+                let (a, b) = (keys.iter().position(|k| *k == g.keys[id]).unwrap(),
+                              keys.iter().position(|k| *k == g.keys[n]).unwrap());
+                assert_eq!(payload as usize, a * 100 + b);
+                stored.insert((a, b));
+            }
+        }
+        assert_eq!(&stored, arcs);
+    }
 
-        assert!(b);
+    #[test]
+    fn removals_keep_storage_consistent() {
+        let keys : Vec<String> = (0..12).map(|k| format!("v{}", k)).collect();
+        let mut g : Graph<u32> = Graph::default();
+        let mut present : Vec<bool> = vec![false; keys.len()];
+        let mut arcs : HashSet<(usize, usize)> = HashSet::new();
+        let mut state : u64 = 7;
+        let mut next = |n : usize| {
+            state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            ((state >> 33) % n as u64) as usize
+        };
+
+        for _ in 0..3000 {
+            let (a, b, op) = (next(keys.len()), next(keys.len()), next(10));
+            let (ka, kb) = (keys[a].as_str(), keys[b].as_str());
+            let edge = present[a] && present[b] && a != b;
+            match op {
+                0..=2 => {
+                    g.add_vertex(keys[a].clone());
+                    present[a] = true;
+                }
+                3 => {
+                    assert_eq!(g.remove_vertex(ka), present[a]);
+                    present[a] = false;
+                    arcs.retain(|&(x, y)| x != a && y != a);
+                }
+                4 | 5 => {
+                    let new = edge && !arcs.contains(&(a, b));
+                    assert_eq!(g.add_directed_edge(ka, kb, (a * 100 + b) as u32), new);
+                    if new {
+                        arcs.insert((a, b));
+                    }
+                }
+                6 => {
+                    // both arcs get the forward payload, which the check doesn't expect
+                    if edge && !arcs.contains(&(a, b)) && !arcs.contains(&(b, a)) {
+                        g.add_directed_edge(ka, kb, (a * 100 + b) as u32);
+                        g.add_directed_edge(kb, ka, (b * 100 + a) as u32);
+                        arcs.insert((a, b));
+                        arcs.insert((b, a));
+                    }
+                }
+                7 => {
+                    assert_eq!(g.remove_directed_edge(ka, kb), arcs.remove(&(a, b)));
+                }
+                _ => {
+                    let removed = arcs.remove(&(a, b)) | arcs.remove(&(b, a));
+                    assert_eq!(g.remove_edge(ka, kb), removed);
+                }
+            }
+            assert_consistent(&g, &arcs, &keys);
+        }
+        assert!(!g.is_empty() && !arcs.is_empty());
     }
 
     #[test]
-    fn two_nodes_mutate() {
-        let cell1 = Vertex::new_cell("one".to_string());
-        let cell2 = Vertex::new_cell("two".to_string());
+    fn removals_free_memory() {
+        let mut g = graph(&[]);
+        for k in 0..100 {
+            g.add_vertex(format!("v{}", k));
+            g.add_edge("v0", &format!("v{}", k));
+        }
+        assert_eq!(g.out[0].len(), 99);
 
-        Vertex::add_neighbor(&cell1, &cell2);
-        {
-            cell2.ptr.borrow_mut().key = "changed".to_string();
+        for k in 1..100 {
+            assert!(g.remove_vertex(&format!("v{}", k)));
         }
+
+        assert_eq!(g.len(), 1);
+        assert!(g.out[0].capacity() < 25);
+        assert!(g.keys.capacity() < 25 && g.out.capacity() < 25 && g.inc.capacity() < 25);
+    }
+
+    #[test]
+    fn two_nodes_keys_interned() {
+        let mut g = graph(&["one", "two"]);
+
+        g.add_vertex("one".to_string());
+        g.add_edge("one", "two");
+
+        assert_eq!(g.keys, vec!["one".to_string(), "two".to_string()]);
+        assert_eq!(g.ids["two"], 1);
     }
 }
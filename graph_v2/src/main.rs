@@ -1,4 +1,4 @@
-#[doc="
+#![doc="
     Authors: Adel and James, Whang and Lahlou.
     NetIDs: syw973, adl538
 
@@ -8,29 +8,41 @@
     The implementation was decided upon to maximize learning. We could've done a simple adjancency matrix
     or adjacency, or even a HashMap<String, Vec<String>> since the homework didn't say the nodes needed to be mutable.
     Instead, we wrote a few different implementations, some that relied on heavy use of life times,
-    generics (which needed PhatomData markers). Ultimately, we went with a representation to get
-    practice using reference types other than box, specifically RefCell and Rc. We also practiced
-    using Traits.
+    generics (which needed PhatomData markers), and one where vertices held RefCell and Rc pointers to
+    each other. Those pointer cycles leaked every graph, so vertices are now interned: each key gets an
+    integer id, and adjacency is a sorted vector of neighbour ids per vertex.
 
-    Otherwise, we made choices related to performance. HashMap allows O(1) access to any node by unique
-    hashed key value. HashSets for adjancency prohibits duplicates (though apparently is super unstable).
-    While slightly more expensive operation wise, space wise, using HashSets is similar to adjacency lists.
-    The VertexCell struct was made to wrap our reference for convenience, so that we could implementations
-    traits.
+    Otherwise, we made choices related to performance. HashMap allows O(1) access to any id by unique
+    hashed key value, and searches run on ids in flat vectors without hashing strings. Sorted adjacency
+    prohibits duplicates with a binary search and costs little more space than a plain adjacency list.
 
     Comments:
-        - Structs Vertex was not exposed by pub. Means, direct vertex level operations not allowed
-        - Structs don't derive debug because they cause an infinite print loops and overrun the stack
+        - Vertices are not exposed by pub. Means, direct vertex level operations not allowed
+
+    Graph file:
+        Each line is a chain of names, and each name shares an edge with the one before it.
+        Between two names, a connector can set the direction and weight of their edge:
+            a b         undirected, weight 1
+            a -- b      undirected, weight 1
+            a -> b      from a to b only, weight 1
+            a -2.5- b   undirected, weight 2.5
+            a -2.5-> b  from a to b only, weight 2.5
+        so `a -> b -3- c d` is a valid line. Weights can't be negative, and names can't look
+        like connectors.
 
     Assumptions:
         - Simple graph aka nodes can't share edges with themself
         - Keys are only Strings
         - We want only unique keys
         - We want any path from origin to destination vertex
-        - Graph is undirected
+        - Edges are undirected with weight 1 unless the file says otherwise
         - Graph vertices must be mutable
         - We do nothing when asked for a path with only one node specified
         - We alert the user when there is no path
+
+    Usage: cargo run graph.dat [--costs]
+        Without --costs, a query gets a path with the fewest edges. With it, the query gets the
+        cheapest path by edge weight and its total cost, as in `a b d (cost 3.5)`.
 "]
 
 extern crate graph_v2;
@@ -42,52 +54,108 @@ use graph_v2::graph::{Graph};
 
 
 fn main() {
-    let inputfile = process_commandline();
+    let (inputfile, costs) = process_commandline();
     let graph = process_file(inputfile);
-    handle_queries(stdin(), &graph);
+    handle_queries(stdin(), &graph, costs);
 }
 
-fn process_file<R : Read>(input: R) -> Graph {
-    let mut graph : Graph = Graph::new();
-    let mut previous : Option<String> = None;
+fn process_file<R : Read>(input: R) -> Graph<f64> {
+    let mut graph : Graph<f64> = Graph::default();
     let reader = BufReader::new(input);
 
-    for line in reader.lines() {
-        //iterate through tokens seperated by whitespace aka token names
-        for node in line.unwrap().split_whitespace() {
-            graph.add_vertex(node.to_string());
-            if let Some(pnode) = previous {
-                graph.add_edge(&pnode, &node.to_string());
+    for (number, line) in reader.lines().enumerate() {
+        if let Err(message) = add_line(&mut graph, &line.unwrap()) {
+            panic!("graph file line {}: {}", number + 1, message);
+        }
+    }
+
+    graph
+}
+
+/// Weight of the edges the graph file doesn't give one
+const DEFAULT_WEIGHT : f64 = 1.0;
+
+/// How two consecutive names on a line of the graph file are joined
+#[derive(Debug, PartialEq)]
+struct Link {
+    directed : bool,
+    weight : f64,
+}
+
+impl Default for Link {
+    fn default() -> Link {
+        Link { directed: false, weight: DEFAULT_WEIGHT }
+    }
+}
+
+/// Adds the vertices and edges of one line of the graph file
+/// @return Result<(), String> - what is wrong with the line, if anything
+fn add_line(graph : &mut Graph<f64>, line : &str) -> Result<(), String> {
+    let mut previous : Option<&str> = None;
+    let mut link : Option<Link> = None;
+
+    //iterate through tokens seperated by whitespace aka token names and connectors
+    for token in line.split_whitespace() {
+        match parse_link(token) {
+            Some(parsed) => {
+                if previous.is_none() || link.is_some() {
+                    return Err(format!("'{}' must be between two names", token));
+                }
+                link = Some(parsed?);
             }
+            None => {
+                graph.add_vertex(token.to_string());
+                if let Some(pnode) = previous {
+                    let Link { directed, weight } = link.take().unwrap_or_default();
+                    if directed {
+                        graph.add_directed_edge(pnode, token, weight);
+                    } else {
+                        graph.add_weighted_edge(pnode, token, weight);
+                    }
+                }
 
-            previous = Some(node.to_string());
+                previous = Some(token);
+            }
         }
+    }
 
-        previous = None;
+    match link {
+        Some(_) => Err("the line ends with a connector".to_string()),
+        None => Ok(()),
     }
+}
 
-    graph
+/// Reads a connector: `--` or `->`, or either with a weight between the dashes, `-2.5-` or `-2.5->`
+/// @return Option<Result<Link, String>> - None if the token is a name
+fn parse_link(token : &str) -> Option<Result<Link, String>> {
+    let (directed, weight) = if token == "--" || token == "->" {
+        (token == "->", "")
+    } else if token.len() > 3 && token.starts_with('-') && token.ends_with("->") {
+        (true, &token[1..token.len() - 2])
+    } else if token.len() > 2 && token.starts_with('-') && token.ends_with('-') {
+        (false, &token[1..token.len() - 1])
+    } else {
+        return None;
+    };
+
+    if weight.is_empty() {
+        return Some(Ok(Link { directed, weight: DEFAULT_WEIGHT }));
+    }
+    Some(match weight.parse::<f64>() {
+        Ok(weight) if weight >= 0.0 && weight.is_finite() => Ok(Link { directed, weight }),
+        _ => Err(format!("'{}' is not a non-negative weight in '{}'", weight, token)),
+    })
 }
 
 
-fn handle_queries<R: Read>(input : R, graph : &Graph) {
+fn handle_queries<R: Read>(input : R, graph : &Graph<f64>, costs : bool) {
     let mut previous  : Option<String> = None;
     let reader = BufReader::new(input);
 
     for line in reader.lines() {
         for node in line.unwrap().split_whitespace() {
             if let Some(pnode) = previous {
-                let path = graph.find_path(pnode.clone(), node.to_string());
-                if path.len() == 0 {
-                    println!("There is no path from {} to {}", pnode.clone(), node.to_string());
-                } else {
-                    let mut path_string = "".to_string();
-                    for node in path {
-                        path_string.push_str(&node);
-                        path_string.push_str(" ");
-                    }
-                    println!("{}", path_string);
-                }
+                println!("{}", answer_query(graph, &pnode, node, costs));
             }
 
             previous = Some(node.to_string());
@@ -97,23 +165,54 @@ fn handle_queries<R: Read>(input : R, graph : &Graph) {
     }
 }
 
+/// Path from one node to another, or that there is none
+/// @param costs : bool - whether to find the cheapest path and show its cost, rather than the
+/// one with the fewest edges. Either way a node has no path to itself, as in find_path.
+fn answer_query(graph : &Graph<f64>, src : &str, dst : &str, costs : bool) -> String {
+    let (path, cost) = if src == dst {
+        (Vec::new(), None)
+    } else if costs {
+        match graph.shortest_path_weighted(src, dst) {
+            Some((path, cost)) => (path, Some(cost)),
+            None => (Vec::new(), None),
+        }
+    } else {
+        (graph.find_path(src.to_string(), dst.to_string()), None)
+    };
+
+    if path.is_empty() {
+        return format!("There is no path from {} to {}", src, dst);
+    }
+    let mut path_string = "".to_string();
+    for node in path {
+        path_string.push_str(&node);
+        path_string.push(' ');
+    }
+    if let Some(cost) = cost {
+        path_string.push_str(&format!("(cost {})", cost));
+    }
+    path_string
+}
+
 
-fn process_commandline() -> BufReader<File> {
+/// @return (BufReader<File>, bool) - the graph file, and whether queries are answered with costs
+fn process_commandline() -> (BufReader<File>, bool) {
     let args : Vec<_>= std::env::args().collect();
 
-    if args.len() != 2 {
-        panic!("usage: cargo run graph.dat");
+    let costs = args.len() == 3 && args[2] == "--costs";
+    if args.len() != 2 && !costs {
+        panic!("usage: cargo run graph.dat [--costs]");
     }
 
     let mut filename = args[1].clone();
     let base_path = Path::new(&args[0]).parent().unwrap();
     filename = fix_path(base_path, &filename);
 
-    BufReader::new(open_file(&filename))
+    (BufReader::new(open_file(&filename)), costs)
 }
 
 fn open_file(filename: &String) -> File{
-    if let Ok(file) = File::open(&filename) {
+    if let Ok(file) = File::open(filename) {
         file
     } else {
         panic!("Couldn't open file");
@@ -126,12 +225,12 @@ fn fix_path(base_path : &Path, filename : &String) -> String {
 }
 
 
-/// process_commandline, open_file, and fix_path are from previous assignments
+// process_commandline, open_file, and fix_path are from previous assignments
 
 #[cfg(test)]
 mod graph_usage_tests {
     use std::io::{Read, Result};
-    use super::process_file;
+    use super::{process_file, parse_link, answer_query, Link};
     use graph_v2::graph::{Graph};
 
     struct StringReader {
@@ -158,20 +257,47 @@ mod graph_usage_tests {
                 self.position += 1;
             }
 
-            return Ok(count);
+            Ok(count)
         }
     }
 
-    fn assert_processed(expected : &Graph, input : &str){
+    fn assert_processed<E>(expected : &Graph<E>, input : &str){
         let fake_reader = StringReader::new(input.to_owned());
         let results = process_file(fake_reader);
 
         assert!(expected.len() == results.len());
     }
 
+    fn process(input : &str) -> Graph<f64> {
+        process_file(StringReader::new(input.to_owned()))
+    }
+
+    #[test]
+    fn links() {
+        assert_eq!(parse_link("a"), None);
+        assert_eq!(parse_link("-"), None);
+        assert_eq!(parse_link("->"), Some(Ok(Link { directed: true, weight: 1.0 })));
+        assert_eq!(parse_link("--"), Some(Ok(Link { directed: false, weight: 1.0 })));
+        assert_eq!(parse_link("-2.5->"), Some(Ok(Link { directed: true, weight: 2.5 })));
+        assert_eq!(parse_link("-0-"), Some(Ok(Link { directed: false, weight: 0.0 })));
+        assert!(parse_link("--1-").unwrap().is_err());
+    }
+
+    #[test]
+    fn answers() {
+        let graph = process("a b -0.5- d\na -3-> d\nc");
+        assert_eq!(answer_query(&graph, "a", "d", false), "a d ");
+        assert_eq!(answer_query(&graph, "a", "d", true), "a b d (cost 1.5)");
+        assert_eq!(answer_query(&graph, "d", "a", true), "d b a (cost 1.5)");
+        assert_eq!(answer_query(&graph, "a", "c", true), "There is no path from a to c");
+        assert_eq!(answer_query(&graph, "a", "c", false), "There is no path from a to c");
+        assert_eq!(answer_query(&graph, "a", "a", false), "There is no path from a to a");
+        assert_eq!(answer_query(&graph, "a", "a", true), "There is no path from a to a");
+    }
+
     mod graph_building_test {
         extern crate graph_v2;
-        use super::{assert_processed};
+        use super::{assert_processed, process};
         use graph_v2::graph::{Graph};
 
 
@@ -182,11 +308,42 @@ mod graph_usage_tests {
             expected.add_vertices(vec!["a".to_string(), "c".to_string(),
             "d".to_string()].to_owned());
 
-            expected.add_edge(&"a".to_string(), &"b".to_string());
-            expected.add_edge(&"c".to_string(), &"d".to_string());
-            expected.add_edge(&"a".to_string(), &"d".to_string());
+            expected.add_edge("a", "b");
+            expected.add_edge("c", "d");
+            expected.add_edge("a", "d");
 
             assert_processed(&expected, "a c\nc d\na d")
         }
+
+        #[test]
+        fn directed_and_weighted_build() {
+            let mut expected : Graph<f64> = Graph::default();
+            expected.add_vertices(vec!["a".to_string(), "b".to_string(), "c".to_string(),
+            "d".to_string()]);
+            expected.add_directed_edge("a", "b", 1.0);
+            expected.add_weighted_edge("b", "c", 3.0);
+            expected.add_weighted_edge("c", "d", 1.0);
+            expected.add_directed_edge("d", "a", 0.5);
+
+            assert!(process("a -> b -3- c d\nd -0.5-> a") == expected);
+        }
+
+        #[test]
+        #[should_panic(expected = "graph file line 2: '->' must be between two names")]
+        fn connector_without_name() {
+            process("a b\n-> c");
+        }
+
+        #[test]
+        #[should_panic(expected = "graph file line 1: 'x' is not a non-negative weight in '-x->'")]
+        fn bad_weight() {
+            process("a -x-> b");
+        }
+
+        #[test]
+        #[should_panic(expected = "graph file line 1: the line ends with a connector")]
+        fn dangling_connector() {
+            process("a b --");
+        }
     }
 }